@@ -1,8 +1,10 @@
 use futures::executor::block_on;
 use maybe_unwind::maybe_unwind;
-use mimicaw::{Args, Outcome, Test};
+use mimicaw::{Args, BlockingRunner, Outcome, Test, TestDesc};
 use std::panic::UnwindSafe;
 
+type TestFn = Box<dyn Fn() + Send + Sync + UnwindSafe>;
+
 fn main() {
     maybe_unwind::set_hook();
 
@@ -12,45 +14,38 @@ fn main() {
     let age = 14;
     let gender = "woman";
 
-    let tests: Vec<Test<Box<dyn Fn() + UnwindSafe>>> = vec![
+    let tests: Vec<Test<TestFn>> = vec![
         Test::test(
             "check_name",
-            Box::new(|| {
+            Box::new(move || {
                 assert_eq!(name, "Alice");
             }),
         ),
         Test::test(
             "check_age",
-            Box::new(|| {
+            Box::new(move || {
                 assert_eq!(age, 14);
             }),
         ),
         Test::test(
             "check_gender",
-            Box::new(|| {
+            Box::new(move || {
                 assert_eq!(gender, "man");
             }),
         ),
     ];
 
-    block_on(mimicaw::run_tests(
-        &args,
-        tests,
-        |_desc, f: Box<dyn Fn() + UnwindSafe>| async move {
-            match maybe_unwind(f) {
-                Ok(()) => Outcome::passed(),
-                Err(unwind) => {
-                    let location = unwind
-                        .location()
-                        .map_or("<unknown>".into(), |loc| loc.to_string());
-                    Outcome::failed().error_message(format!(
-                        "[{}] {}",
-                        location,
-                        unwind.payload_str()
-                    ))
-                }
-            }
-        },
-    ))
-    .exit()
+    // The assertions are run on the thread pool, so they don't block
+    // the other test cases running concurrently.
+    let runner = BlockingRunner::new(|_desc: TestDesc, f: TestFn| match maybe_unwind(f) {
+        Ok(()) => Outcome::passed(),
+        Err(unwind) => {
+            let location = unwind
+                .location()
+                .map_or("<unknown>".into(), |loc| loc.to_string());
+            Outcome::failed().error_message(format!("[{}] {}", location, unwind.payload_str()))
+        }
+    });
+
+    block_on(mimicaw::run_tests(&args, tests, runner)).exit()
 }
@@ -1,39 +1,25 @@
-use async_std::task;
 use futures_timer::Delay;
-use mimicaw::{Args, Outcome, Test};
+use mimicaw::{async_std::AsyncStdSpawner, Outcome, SpawnRunner, Test, TestDesc};
 use std::time::Duration;
 
-type TestFn = fn() -> task::JoinHandle<Outcome>;
-
-#[async_std::main]
-async fn main() {
-    let args = Args::from_env().unwrap_or_else(|st| st.exit());
+fn main() {
+    let tests = vec![
+        Test::test("case1", 8),
+        Test::test("case2", 4),
+        Test::test("case3", 6),
+    ];
 
-    let tests: Vec<Test<TestFn>> = vec![
-        Test::test("case1", || {
-            task::spawn(async {
-                Delay::new(Duration::from_secs(8)).await;
-                // do stuff...
-                Outcome::passed()
-            })
-        }),
-        Test::test("case2", || {
-            task::spawn(async {
-                Delay::new(Duration::from_secs(4)).await;
-                // do stuff...
+    let runner = SpawnRunner::new(AsyncStdSpawner::new(), |desc: TestDesc, secs: u64| {
+        async move {
+            Delay::new(Duration::from_secs(secs)).await;
+            // do stuff...
+            if desc.name() == "case2" {
                 Outcome::failed().error_message("foo")
-            })
-        }),
-        Test::test("case3", || {
-            task::spawn(async move {
-                Delay::new(Duration::from_secs(6)).await;
-                // do stuff ...
+            } else {
                 Outcome::passed()
-            })
-        }),
-    ];
+            }
+        }
+    });
 
-    mimicaw::run_tests(&args, tests, |_, test_fn: TestFn| test_fn())
-        .await
-        .exit()
+    mimicaw::async_std::main(tests, runner)
 }
@@ -0,0 +1,57 @@
+use futures::executor::block_on;
+use futures_timer::Delay;
+use mimicaw::{Args, Outcome, Test, TestDesc};
+use std::time::{Duration, Instant};
+
+enum Bench {
+    Sync(fn(u64) -> u64),
+    Async(Duration),
+    Copy(usize),
+}
+
+fn fib(n: u64) -> u64 {
+    match n {
+        0 | 1 => n,
+        n => fib(n - 1) + fib(n - 2),
+    }
+}
+
+fn main() {
+    let args = Args::from_env().unwrap_or_else(|st| st.exit());
+
+    let tests = vec![
+        Test::bench("fib_20", Bench::Sync(fib)),
+        Test::bench("delay_1ms", Bench::Async(Duration::from_millis(1))),
+        Test::test("not_a_bench", Bench::Sync(fib)),
+        Test::test("copy_throughput", Bench::Copy(64 * 1024 * 1024)),
+    ];
+
+    block_on(mimicaw::run_tests(
+        &args,
+        tests,
+        |desc: TestDesc, bench| async move {
+            let mut b = desc.bencher();
+            match bench {
+                Bench::Sync(f) if desc.is_bench() => b.iter(|| f(20)),
+                Bench::Sync(f) => {
+                    if f(10) == 55 {
+                        Outcome::passed()
+                    } else {
+                        Outcome::failed()
+                    }
+                }
+                Bench::Async(dur) => b.iter_async(|| Delay::new(dur)).await,
+                Bench::Copy(len) => {
+                    let src = vec![1u8; len];
+                    let start = Instant::now();
+                    let dst = src.clone();
+                    let elapsed = start.elapsed();
+                    Outcome::passed()
+                        .throughput_bytes(dst.len() as u64, elapsed)
+                        .metric("copied", dst.len() as f64, "bytes")
+                }
+            }
+        },
+    ))
+    .exit()
+}
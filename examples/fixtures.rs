@@ -0,0 +1,40 @@
+use mimicaw::{Outcome, Test, TestDesc};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// Each fixture consists of the numbers on the first line and their sum on the second.
+fn check_sum(path: &Path) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut lines = content.lines();
+    let parse = |line: Option<&str>| -> Result<Vec<i64>, String> {
+        line.unwrap_or_default()
+            .split_whitespace()
+            .map(|n| n.parse().map_err(|e| format!("invalid number: {}", e)))
+            .collect()
+    };
+    let numbers = parse(lines.next())?;
+    let expected = parse(lines.next())?;
+    let actual: i64 = numbers.iter().sum();
+    if expected == [actual] {
+        Ok(())
+    } else {
+        Err(format!("expected {:?}, but got {}", expected, actual))
+    }
+}
+
+fn main() {
+    let tests = Test::from_dir("examples/fixtures/sum", "**/*.txt", |path| {
+        path.to_path_buf()
+    })
+    .expect("failed to read the fixtures");
+
+    mimicaw::main(tests, |desc: TestDesc, path: PathBuf| async move {
+        debug_assert_eq!(desc.file(), Some(path.as_path()));
+        match check_sum(&path) {
+            Ok(()) => Outcome::passed(),
+            Err(msg) => Outcome::failed().error_message(msg),
+        }
+    })
+}
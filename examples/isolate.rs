@@ -0,0 +1,40 @@
+use futures::executor::block_on;
+use mimicaw::{Args, Outcome, Test, TestDesc};
+
+enum Case {
+    Pass,
+    Fail,
+    Abort,
+    Exit(i32),
+}
+
+// Run with `--isolate` so that the aborting test does not take out the others.
+fn main() {
+    let args = Args::from_env().unwrap_or_else(|st| st.exit());
+
+    let tests = vec![
+        Test::test("passes", Case::Pass),
+        Test::test("fails", Case::Fail),
+        Test::test("aborts", Case::Abort),
+        Test::test("exits", Case::Exit(3)),
+        Test::bench("bench", Case::Pass),
+    ];
+
+    block_on(mimicaw::run_tests(
+        &args,
+        tests,
+        |desc: TestDesc, case| async move {
+            match case {
+                Case::Pass if desc.is_bench() => desc.bencher().iter(|| 1 + 1),
+                Case::Pass => Outcome::passed(),
+                Case::Fail => Outcome::failed().error_message("assertion failed"),
+                Case::Abort => {
+                    eprintln!("about to abort");
+                    std::process::abort()
+                }
+                Case::Exit(code) => std::process::exit(code),
+            }
+        },
+    ))
+    .exit();
+}
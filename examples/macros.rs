@@ -0,0 +1,52 @@
+use mimicaw::{Bencher, DefaultRunner, Outcome};
+use std::{error::Error, time::Duration};
+
+#[mimicaw::test]
+async fn async_test() {
+    futures_timer::Delay::new(Duration::from_millis(100)).await;
+}
+
+#[mimicaw::test]
+fn sync_test() -> Outcome {
+    let sum: u32 = [1, 2, 3].iter().sum();
+    if sum == 6 {
+        Outcome::passed()
+    } else {
+        Outcome::failed().error_message(format!("wrong sum: {}", sum))
+    }
+}
+
+#[mimicaw::test]
+fn parse() -> Result<(), Box<dyn Error>> {
+    let n: u32 = "42".parse()?;
+    assert_eq!(n, 42);
+    Ok(())
+}
+
+#[mimicaw::test(should_panic = "explicit")]
+fn panics() {
+    panic!("explicit panic");
+}
+
+#[mimicaw::test(timeout = 0.5, tags("slow"))]
+async fn times_out() {
+    futures_timer::Delay::new(Duration::from_secs(10)).await;
+}
+
+#[mimicaw::test(ignore, name = "custom_name")]
+async fn ignored() {}
+
+#[mimicaw::test(bench)]
+fn sum(mut b: Bencher) -> Outcome {
+    b.iter(|| (0..100u64).sum::<u64>())
+}
+
+mod nested {
+    // The tests are discovered wherever they are defined in the binary.
+    #[mimicaw::test]
+    async fn in_module() {}
+}
+
+fn main() {
+    mimicaw::main(mimicaw::collected_tests(), DefaultRunner::new())
+}
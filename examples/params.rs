@@ -0,0 +1,49 @@
+use mimicaw::{Matrix, Outcome, Test, TestDesc};
+use std::fmt;
+
+enum Case {
+    Parse(&'static str),
+    Add(i64, i64),
+}
+
+// Used for the parameter value in the test name.
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Case::Parse(input) => f.write_str(input),
+            Case::Add(x, y) => write!(f, "{}+{}", x, y),
+        }
+    }
+}
+
+fn main() {
+    let inputs = vec!["42", "-7", "x"];
+    let mut tests = Test::cases("parse", "input", inputs.into_iter().map(Case::Parse));
+
+    // Select a single combination with e.g. `'add[y=-1,x=2]'`, in any order.
+    tests.extend(
+        Matrix::new("add")
+            .param("x", vec![0, 1, 2])
+            .param("y", vec![-1, 1])
+            .tests(|params| {
+                let x = params.get("x").unwrap().parse().unwrap();
+                let y = params.get("y").unwrap().parse().unwrap();
+                Case::Add(x, y)
+            }),
+    );
+
+    mimicaw::main(tests, |desc: TestDesc, case| async move {
+        match case {
+            Case::Parse(input) => match input.parse::<i64>() {
+                Ok(..) => Outcome::passed(),
+                Err(err) => {
+                    Outcome::failed().error_message(format!("{}: {:?}", err, desc.params()))
+                }
+            },
+            Case::Add(x, y) if x + y >= 0 => Outcome::passed(),
+            Case::Add(x, y) => {
+                Outcome::failed().error_message(format!("{} + {} is negative", x, y))
+            }
+        }
+    })
+}
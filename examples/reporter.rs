@@ -0,0 +1,93 @@
+use futures::executor::block_on;
+use futures_timer::Delay;
+use mimicaw::{Args, Outcome, OutcomeKind, PrettyReporter, Report, Reporter, Test, TestDesc};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// A reporter that records the execution time of tests, and lists the slowest
+/// ones after the summary printed by `PrettyReporter`.
+struct Timings {
+    pretty: PrettyReporter,
+    started: HashMap<String, Instant>,
+    elapsed: Vec<(String, Duration, &'static str)>,
+}
+
+impl Reporter for Timings {
+    fn suite_start(&mut self, tests: &[TestDesc]) {
+        self.pretty.suite_start(tests);
+    }
+
+    fn test_start(&mut self, desc: &TestDesc) {
+        self.started.insert(desc.name().to_owned(), Instant::now());
+    }
+
+    fn test_result(&mut self, desc: &TestDesc, outcome: Option<&Outcome>) {
+        self.pretty.test_result(desc, outcome);
+        if let (Some(started), Some(outcome)) = (self.started.remove(desc.name()), outcome) {
+            let status = match outcome.kind() {
+                OutcomeKind::Failed => "FAILED",
+                _ => "ok",
+            };
+            self.elapsed
+                .push((desc.name().to_owned(), started.elapsed(), status));
+        }
+    }
+
+    fn summary(&mut self, report: &Report) {
+        self.pretty.summary(report);
+
+        self.elapsed
+            .sort_by_key(|&(_, elapsed, _)| Reverse(elapsed));
+        println!();
+        println!("slowest tests:");
+        for (name, elapsed, status) in self.elapsed.iter().take(3) {
+            println!("    {:<8} {:>6.3}s {}", name, elapsed.as_secs_f64(), status);
+        }
+    }
+
+    fn warning(&mut self, msg: &dyn fmt::Display) {
+        self.pretty.warning(msg);
+    }
+
+    fn error(&mut self, msg: &dyn fmt::Display) {
+        self.pretty.error(msg);
+    }
+}
+
+fn main() {
+    let args = Args::from_env().unwrap_or_else(|st| st.exit());
+
+    let tests = vec![
+        Test::test("fast", 10),
+        Test::test("medium", 200),
+        Test::test("slow", 500),
+        Test::test("broken", 50),
+    ];
+
+    let mut reporter = Timings {
+        pretty: PrettyReporter::new(&args),
+        started: HashMap::new(),
+        elapsed: vec![],
+    };
+
+    let report = block_on(mimicaw::run_tests_with_reporter(
+        &args,
+        tests,
+        |desc: TestDesc, millis| async move {
+            Delay::new(Duration::from_millis(millis)).await;
+            if desc.name() == "broken" {
+                Outcome::failed().error_message("something went wrong")
+            } else {
+                Outcome::passed()
+            }
+        },
+        &mut reporter,
+    ))
+    .unwrap_or_else(|st| st.exit());
+
+    report.status().exit()
+}
@@ -0,0 +1,27 @@
+use mimicaw::{Test, TestDesc};
+
+// Run with `--bless` to rewrite the snapshots after changing the output.
+fn render_table(rows: &[(&str, u32)]) -> String {
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    rows.iter()
+        .map(|(name, value)| format!("{:<width$} | {:>5}\n", name, value, width = width))
+        .collect()
+}
+
+fn main() {
+    let tests = vec![
+        Test::test(
+            "fruits",
+            vec![("apple", 3), ("banana", 12), ("cherry", 150)],
+        ),
+        Test::test("empty", vec![]),
+    ];
+
+    mimicaw::main(
+        tests,
+        |desc: TestDesc, rows: Vec<(&'static str, u32)>| async move {
+            let actual = render_table(&rows);
+            desc.check_snapshot(format!("examples/snapshots/{}.txt", desc.name()), actual)
+        },
+    )
+}
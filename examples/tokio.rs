@@ -1,41 +1,34 @@
-use mimicaw::{Args, Outcome, Test};
-use tokio::task;
+use mimicaw::{tokio::TokioSpawner, Outcome, SpawnRunner, Test, TestDesc};
+use std::time::{Duration, Instant};
 
-type TestFn = fn() -> task::JoinHandle<Outcome>;
-
-#[tokio::main]
-async fn main() {
-    let args = Args::from_env().unwrap_or_else(|st| st.exit());
+// Busy-wait so that the parallelism of the test cases is observable.
+fn spin(duration: Duration) {
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        std::hint::spin_loop();
+    }
+}
 
-    let tests: Vec<Test<TestFn>> = vec![
-        Test::test("case1", || {
-            task::spawn(async {
-                tokio::time::delay_for(tokio::time::Duration::from_secs(10)).await;
-                // do stuff...
-                Outcome::passed()
-            })
-        }),
-        Test::test("case2", || {
-            task::spawn(async {
-                tokio::time::delay_for(tokio::time::Duration::from_secs(6)).await;
-                // do stuff...
-                Outcome::failed().error_message("foo")
-            })
-        }),
-        Test::<TestFn>::test("case3_a_should_be_zero", || {
-            task::spawn(async move {
-                tokio::time::delay_for(tokio::time::Duration::from_secs(3)).await;
-                // do stuff ...
-                Outcome::passed()
-            })
-        })
-        .ignore(true),
+fn main() {
+    let tests = vec![
+        Test::test("case1", Duration::from_secs(3)),
+        Test::test("case2", Duration::from_secs(2)),
+        Test::test("case3_a_should_be_zero", Duration::from_secs(1)).ignore(true),
+        Test::test("case4_panics", Duration::from_secs(0)),
     ];
 
-    mimicaw::run_tests(&args, tests, |_, test_fn: TestFn| {
-        let handle = test_fn();
-        async move { handle.await.unwrap() }
-    })
-    .await
-    .exit()
+    // The test cases are spawned onto the worker threads of the runtime,
+    // so the CPU-bound ones are run in parallel.
+    let runner = SpawnRunner::new(
+        TokioSpawner::new(),
+        |desc: TestDesc, duration: Duration| async move {
+            spin(duration);
+            if desc.name().ends_with("panics") {
+                panic!("explicit panic");
+            }
+            Outcome::passed()
+        },
+    );
+
+    mimicaw::tokio::main(tests, runner)
 }
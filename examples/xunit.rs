@@ -23,7 +23,17 @@ fn main() -> io::Result<()> {
         |_desc, data| async move {
             match data {
                 "foo" | "baz" => Outcome::passed(),
-                "bar" => Outcome::failed().error_message("`bar' is forbidden"),
+                "bar" => Outcome::failed()
+                    .error_message("`bar' is forbidden")
+                    .attach("input.txt", data.as_bytes()),
+                "The quick brown fox jumps over the lazy dog." => Outcome::mismatch(
+                    "The quick\nbrown fox\njumps over\nthe lazy dog.\n",
+                    data.split(' ')
+                        .collect::<Vec<_>>()
+                        .chunks(2)
+                        .map(|words| words.join(" ") + "\n")
+                        .collect::<String>(),
+                ),
                 data => Outcome::failed().error_message(format!("unknown data: {}", data)),
             }
         },
@@ -63,16 +73,37 @@ fn report_to_xml(report: &Report, path: impl AsRef<Path>) -> io::Result<()> {
                 testsuite.append_child(testcase);
             }
 
-            for (desc, msg) in &report.failed {
+            for (desc, failure) in &report.failed {
                 let testcase = doc.create_element("testcase");
                 testcase.set_attribute_value("name", desc.name());
                 testcase.append_child({
-                    let failure = doc.create_element("failure");
-                    if let Some(msg) = msg {
-                        failure.set_attribute_value("message", &*msg);
+                    let element = doc.create_element("failure");
+                    if let Some(ref msg) = failure.message {
+                        element.set_attribute_value("message", msg);
                     }
-                    failure
+                    // JUnit has no dedicated elements for the compared values,
+                    // so they are written to the body of <failure>.
+                    if let Some(ref mismatch) = failure.mismatch {
+                        element.append_child(doc.create_text(&format!(
+                            "expected:\n{}\nactual:\n{}",
+                            mismatch.expected, mismatch.actual
+                        )));
+                    }
+                    element
                 });
+                // The attachments are referenced in the format recognized by
+                // the JUnit Attachments plugin of Jenkins.
+                if !failure.artifacts.is_empty() {
+                    testcase.append_child({
+                        let system_out = doc.create_element("system-out");
+                        for path in &failure.artifacts {
+                            system_out.append_child(
+                                doc.create_text(&format!("[[ATTACHMENT|{}]]\n", path.display())),
+                            );
+                        }
+                        system_out
+                    });
+                }
                 testsuite.append_child(testcase);
             }
 
@@ -0,0 +1,224 @@
+/*!
+Procedural macros for `mimicaw`.
+
+This crate is not intended to be used directly.
+Enable the `macros` feature of `mimicaw` and use `#[mimicaw::test]` instead.
+!*/
+
+#![deny(missing_docs)]
+#![forbid(unsafe_code)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, AttributeArgs, Error, ItemFn, Lit, LitStr, Meta, MetaNameValue, NestedMeta,
+};
+
+/// Define a test case run by `mimicaw::DefaultRunner`.
+///
+/// The annotated function is replaced with the function of the same name
+/// that returns `mimicaw::Test<mimicaw::TestCase>`, and the test is registered
+/// to be returned from `mimicaw::collected_tests()`. The test body may be
+/// either an `async fn` or a normal function, and may return `()`,
+/// `Result<(), E>` or `mimicaw::Outcome`.
+///
+/// The following options are accepted:
+///
+/// * `ignore` - mark the test as ignored.
+/// * `bench` - define a benchmark. The function takes a `mimicaw::Bencher`
+///   and returns `mimicaw::Outcome`.
+/// * `timeout = SECS` - fail the test if it does not complete in time.
+/// * `should_panic`, `should_panic = "message"` or
+///   `should_panic(expected = "message")` - the test passes only if it panics.
+/// * `tags("tag", ...)` - attach the tags to the test.
+/// * `name = "name"` - use the custom name instead of the path of the function
+///   relative to the crate root, like `module::function`.
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let item = parse_macro_input!(item as ItemFn);
+    match Options::parse(args).and_then(|options| expand(options, item)) {
+        Ok(expanded) => expanded.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    ignore: bool,
+    bench: bool,
+    timeout: Option<f64>,
+    should_panic: Option<Option<LitStr>>,
+    tags: Vec<LitStr>,
+    name: Option<LitStr>,
+}
+
+impl Options {
+    fn parse(args: AttributeArgs) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for arg in args {
+            let meta = match arg {
+                NestedMeta::Meta(meta) => meta,
+                NestedMeta::Lit(lit) => return Err(Error::new_spanned(lit, "unexpected literal")),
+            };
+            match meta {
+                Meta::Path(ref path) if path.is_ident("ignore") => options.ignore = true,
+                Meta::Path(ref path) if path.is_ident("bench") => options.bench = true,
+                Meta::Path(ref path) if path.is_ident("should_panic") => {
+                    options.should_panic = Some(None)
+                }
+                Meta::NameValue(ref nv) if nv.path.is_ident("should_panic") => {
+                    options.should_panic = Some(Some(lit_str(nv)?));
+                }
+                Meta::List(ref list) if list.path.is_ident("should_panic") => {
+                    let mut expected = None;
+                    for nested in &list.nested {
+                        match nested {
+                            NestedMeta::Meta(Meta::NameValue(ref nv))
+                                if nv.path.is_ident("expected") =>
+                            {
+                                expected = Some(lit_str(nv)?);
+                            }
+                            nested => {
+                                return Err(Error::new_spanned(
+                                    nested,
+                                    "expected `expected = \"...\"`",
+                                ))
+                            }
+                        }
+                    }
+                    options.should_panic = Some(expected);
+                }
+                Meta::NameValue(ref nv) if nv.path.is_ident("timeout") => {
+                    let secs = match nv.lit {
+                        Lit::Int(ref n) => n.base10_parse::<u64>()? as f64,
+                        Lit::Float(ref n) => n.base10_parse::<f64>()?,
+                        ref lit => {
+                            return Err(Error::new_spanned(
+                                lit,
+                                "the timeout must be a number of seconds",
+                            ))
+                        }
+                    };
+                    options.timeout = Some(secs);
+                }
+                Meta::List(ref list) if list.path.is_ident("tags") => {
+                    for nested in &list.nested {
+                        match nested {
+                            NestedMeta::Lit(Lit::Str(ref tag)) => options.tags.push(tag.clone()),
+                            nested => {
+                                return Err(Error::new_spanned(
+                                    nested,
+                                    "tags must be string literals",
+                                ))
+                            }
+                        }
+                    }
+                }
+                Meta::NameValue(ref nv) if nv.path.is_ident("name") => {
+                    options.name = Some(lit_str(nv)?);
+                }
+                meta => return Err(Error::new_spanned(meta, "unknown option")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn lit_str(nv: &MetaNameValue) -> syn::Result<LitStr> {
+    match nv.lit {
+        Lit::Str(ref s) => Ok(s.clone()),
+        ref lit => Err(Error::new_spanned(lit, "expected a string literal")),
+    }
+}
+
+fn expand(options: Options, item: ItemFn) -> syn::Result<TokenStream2> {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &sig.generics,
+            "the test function must not be generic",
+        ));
+    }
+    let num_inputs = if options.bench { 1 } else { 0 };
+    if sig.inputs.len() != num_inputs {
+        return Err(Error::new_spanned(
+            &sig.inputs,
+            if options.bench {
+                "the benchmark function must take a `mimicaw::Bencher`"
+            } else {
+                "the test function must not take any arguments"
+            },
+        ));
+    }
+
+    let ident = &sig.ident;
+    let name = match options.name {
+        Some(name) => quote!(#name),
+        None => {
+            let ident = ident.to_string();
+            quote!(::mimicaw::__private::test_name(::std::module_path!(), #ident))
+        }
+    };
+
+    let (desc, call) = if options.bench {
+        (quote!(desc), quote!(#ident(desc.bencher())))
+    } else {
+        (quote!(_), quote!(#ident()))
+    };
+    let case = if sig.asyncness.is_some() {
+        quote! {
+            ::mimicaw::TestCase::from_async(|#desc: ::mimicaw::TestDesc| async move {
+                ::mimicaw::Outcome::from(#call.await)
+            })
+        }
+    } else {
+        quote! {
+            ::mimicaw::TestCase::from_blocking(|#desc: ::mimicaw::TestDesc| {
+                ::mimicaw::Outcome::from(#call)
+            })
+        }
+    };
+
+    let mut modifiers = vec![];
+    if let Some(secs) = options.timeout {
+        modifiers.push(quote!(.timeout(::std::time::Duration::from_secs_f64(#secs))));
+    }
+    match options.should_panic {
+        Some(Some(expected)) => modifiers.push(quote!(.should_panic_with(#expected))),
+        Some(None) => modifiers.push(quote!(.should_panic())),
+        None => (),
+    }
+
+    let constructor = if options.bench {
+        quote!(bench)
+    } else {
+        quote!(test)
+    };
+    let ignore = options.ignore;
+    let tags = &options.tags;
+
+    Ok(quote! {
+        #vis fn #ident() -> ::mimicaw::Test<::mimicaw::TestCase> {
+            #(#attrs)*
+            #sig #block
+
+            ::mimicaw::Test::#constructor(#name, #case #(#modifiers)*)
+                .ignore(#ignore)
+                #(.tag(#tags))*
+        }
+
+        ::mimicaw::__private::inventory::submit! {
+            ::mimicaw::__private::Registration::new(#ident)
+        }
+    })
+}
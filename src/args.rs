@@ -1,10 +1,12 @@
 #![allow(missing_docs)]
 
-use crate::ExitStatus;
-use getopts::Options;
+use crate::{bench::BenchOptions, isolate, params, test::TestDesc, ExitStatus};
+use getopts::{Matches, Options};
 use std::{
+    ffi::OsString,
     path::{Path, PathBuf},
     str::FromStr,
+    time::Duration,
 };
 
 /// Command line arguments.
@@ -17,12 +19,29 @@ pub struct Args {
     pub run_ignored: bool,
     pub run_tests: bool,
     pub run_benchmarks: bool,
+    pub run_benchmarks_once: bool,
     pub logfile: Option<PathBuf>,
     pub nocapture: bool,
     pub color: ColorConfig,
     pub format: OutputFormat,
     pub test_threads: Option<usize>,
+    pub isolate: bool,
+    pub max_fail: Option<usize>,
+    pub shard: Option<(usize, usize)>,
+    pub shard_timings: Option<PathBuf>,
+    pub save_timings: Option<PathBuf>,
     pub skip: Vec<String>,
+    pub progress: bool,
+    pub warn_slow: Option<Duration>,
+    pub bench_time: Duration,
+    pub warmup: Duration,
+    pub save_baseline: Option<String>,
+    pub baseline: Option<String>,
+    pub bench_threshold: Option<f64>,
+    pub bless: bool,
+    pub duplicates: DuplicatePolicy,
+    pub artifacts_dir: Option<PathBuf>,
+    pub(crate) isolated_child: bool,
 }
 
 impl Args {
@@ -42,12 +61,10 @@ impl Args {
         }
     }
 
-    pub(crate) fn is_filtered(&self, name: &str) -> bool {
+    pub(crate) fn is_filtered(&self, desc: &TestDesc) -> bool {
+        let name = desc.name();
         if let Some(ref filter) = self.filter {
-            if self.filter_exact && name != filter {
-                return true;
-            }
-            if !name.contains(filter) {
+            if !self.matches_filter(desc, filter) {
                 return true;
             }
         }
@@ -63,6 +80,182 @@ impl Args {
 
         false
     }
+
+    fn matches_filter(&self, desc: &TestDesc, filter: &str) -> bool {
+        let matches = |name: &str, filter: &str| {
+            if self.filter_exact {
+                name == filter
+            } else {
+                name.contains(filter)
+            }
+        };
+        if matches(desc.name(), filter) {
+            return true;
+        }
+
+        // The filter of the form `base[key=value,...]` selects the parameterized
+        // tests having all the specified parameters, regardless of their order.
+        match (params::parse_filter(filter), desc.base_name()) {
+            (Some((base, params)), Some(base_name)) => {
+                matches(base_name, base)
+                    && params
+                        .iter()
+                        .all(|(key, value)| desc.params().get(key) == Some(value.as_str()))
+            }
+            _ => false,
+        }
+    }
+
+    /// Return the command line arguments of the child process that runs the test
+    /// case in isolation.
+    ///
+    /// The options that affect how the test case runs are passed down, while the
+    /// output is replaced with the JSON lines read by the parent.
+    pub(crate) fn child_args(&self, desc: &TestDesc) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![
+            "--format=json".into(),
+            "--color=never".into(),
+            "--warn-slow=0".into(),
+        ];
+        // The parent has already decided to run the test case, so the child
+        // must not skip it.
+        if desc.ignored() {
+            args.push("--ignored".into());
+        }
+        if self.bless {
+            args.push("--bless".into());
+        }
+        // The child must give the same names to the duplicated tests.
+        if self.duplicates == DuplicatePolicy::Suffix {
+            args.push("--duplicates=suffix".into());
+        }
+        // The attachments are saved by the child.
+        if let Some(ref dir) = self.artifacts_dir {
+            args.push("--artifacts-dir".into());
+            args.push(dir.into());
+        }
+        if desc.is_bench() {
+            args.push(
+                if self.run_benchmarks {
+                    "--bench"
+                } else {
+                    "--test"
+                }
+                .into(),
+            );
+            args.push(format!("--bench-time={}", self.bench_time.as_secs_f64()).into());
+            args.push(format!("--warmup={}", self.warmup.as_secs_f64()).into());
+        }
+        args.push("--exact".into());
+        args.push("--".into());
+        args.push(desc.name().into());
+        args
+    }
+
+    pub(crate) fn bench_options(&self) -> BenchOptions {
+        BenchOptions {
+            bench_time: self.bench_time,
+            warmup: self.warmup,
+            smoke_test: self.run_benchmarks_once,
+        }
+    }
+}
+
+const DEFAULT_WARN_SLOW: Duration = Duration::from_secs(60);
+
+fn parse_seconds(
+    matches: &Matches,
+    name: &str,
+) -> Result<Option<Duration>, Box<dyn std::error::Error>> {
+    let value = match matches.opt_str(name) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let secs: f64 = value.parse().map_err(|e| {
+        format!(
+            "argument for --{} must be a number of seconds (error: {})",
+            name, e
+        )
+    })?;
+    if !secs.is_finite() || secs < 0.0 {
+        return Err(format!("argument for --{} must not be negative", name).into());
+    }
+    Ok(Some(Duration::from_secs_f64(secs)))
+}
+
+/// Return whether the value of a boolean environment variable is `1` or `true`.
+fn is_enabled(val: &str) -> bool {
+    val == "1" || val.eq_ignore_ascii_case("true")
+}
+
+/// Parse the name of a baseline, which is used as the file name in the data directory.
+fn parse_baseline_name(
+    matches: &Matches,
+    name: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let value = match matches.opt_str(name) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    if value.is_empty()
+        || value == "."
+        || value == ".."
+        || value.chars().any(std::path::is_separator)
+    {
+        return Err(format!(
+            "argument for --{} must be a name without path separators (was {})",
+            name, value
+        )
+        .into());
+    }
+    Ok(Some(value))
+}
+
+/// The 1-based index and the total number of shards.
+struct ShardArg(usize, usize);
+
+impl FromStr for ShardArg {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("argument for --shard must be INDEX/TOTAL (was {})", s);
+        let mut parts = s.splitn(2, '/');
+        let index: usize = parts
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(invalid)?;
+        let total: usize = parts
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(invalid)?;
+        if index == 0 || index > total {
+            return Err(format!(
+                "argument for --shard must satisfy 1 <= INDEX <= TOTAL (was {})",
+                s
+            )
+            .into());
+        }
+        Ok(Self(index, total))
+    }
+}
+
+struct MaxFail(usize);
+
+impl FromStr for MaxFail {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let n = s.parse().map_err(|e| {
+            format!(
+                "argument for --max-fail must be a number > 0 (error: {})",
+                e
+            )
+        })?;
+        if n == 0 {
+            return Err("argument for --max-fail must not be 0".into());
+        }
+        Ok(Self(n))
+    }
 }
 
 struct TestThreads(usize);
@@ -84,6 +277,26 @@ impl FromStr for TestThreads {
     }
 }
 
+/// The threshold of regressions in percent.
+struct Threshold(f64);
+
+impl FromStr for Threshold {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let n: f64 = s.trim_end_matches('%').parse().map_err(|e| {
+            format!(
+                "argument for --bench-threshold must be a percentage such as 5% (error: {})",
+                e
+            )
+        })?;
+        if !n.is_finite() || n < 0.0 {
+            return Err("argument for --bench-threshold must not be negative".into());
+        }
+        Ok(Self(n))
+    }
+}
+
 /// The color configuration.
 #[derive(Copy, Clone, Debug, PartialEq)]
 #[non_exhaustive]
@@ -136,6 +349,32 @@ impl FromStr for OutputFormat {
     }
 }
 
+/// The handling of the tests sharing the same name.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DuplicatePolicy {
+    /// Report all the duplicated names and abort the test run.
+    Error,
+    /// Rename the duplicated tests with a numeric suffix, like `name#2`.
+    Suffix,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(DuplicatePolicy::Error),
+            "suffix" => Ok(DuplicatePolicy::Suffix),
+            s => Err(format!(
+                "argument for --duplicates must be error or suffix (was {})",
+                s
+            )
+            .into()),
+        }
+    }
+}
+
 struct Parser {
     args: Vec<String>,
     opts: Options,
@@ -146,7 +385,11 @@ impl Parser {
         let mut opts = Options::new();
         opts.optflag("", "ignored", "Run only ignored tests");
         opts.optflag("", "test", "Run tests and not benchmarks");
-        opts.optflag("", "bench", "Run benchmarks instead of tests");
+        opts.optflag(
+            "",
+            "bench",
+            "Run benchmarks instead of tests. Without this flag, each benchmark is run once as a test",
+        );
         opts.optflag("", "list", "List all tests and benchmarks");
         opts.optflag("h", "help", "Display this message (longer with --help)");
         opts.optopt(
@@ -169,17 +412,113 @@ impl Parser {
              (placeholder, not implemented yet)",
             "n_threads",
         );
+        opts.optflag(
+            "",
+            "isolate",
+            "Run each test in a separate process, so that crashes are reported as failures",
+        );
+        opts.optflag(
+            "",
+            "fail-fast",
+            "Stop the test run after the first failure. Alias to --max-fail=1",
+        );
+        opts.optopt(
+            "",
+            "max-fail",
+            "Stop the test run after N tests have failed",
+            "N",
+        );
         opts.optmulti(
             "",
             "skip",
             "Skip tests whose names contain FILTER (this flag can be used multiple times)",
             "FILTER",
         );
+        opts.optopt(
+            "",
+            "bench-time",
+            "Measurement time of each benchmark in seconds (default: 3)",
+            "SECS",
+        );
+        opts.optopt(
+            "",
+            "warmup",
+            "Warm-up time of each benchmark in seconds (default: 1)",
+            "SECS",
+        );
+        opts.optopt(
+            "",
+            "save-baseline",
+            "Save the benchmark results as the baseline named NAME",
+            "NAME",
+        );
+        opts.optopt(
+            "",
+            "baseline",
+            "Compare the benchmark results with the baseline named NAME",
+            "NAME",
+        );
+        opts.optopt(
+            "",
+            "bench-threshold",
+            "Fail if a benchmark gets slower than the baseline by more than PCT percent.
+             Requires --baseline",
+            "PCT",
+        );
+        opts.optflag(
+            "",
+            "bless",
+            "Rewrite the mismatched snapshots with the actual values. Also enabled by MIMICAW_BLESS=1 or true",
+        );
+        opts.optopt(
+            "",
+            "artifacts-dir",
+            "Save the attachments of failed tests into DIR (default: target/mimicaw/artifacts)",
+            "DIR",
+        );
+        opts.optopt(
+            "",
+            "duplicates",
+            "Configure handling of tests with the same name:
+                error  = report all of them and abort (default);
+                suffix = rename them with a numeric suffix, like name#2",
+            "error|suffix",
+        );
         opts.optflag(
             "q",
             "quiet",
             "Display one character per test instead of one line. Alias to --format=terse",
         );
+        opts.optopt(
+            "",
+            "shard",
+            "Run only the INDEX-th of TOTAL partitions of the tests (1-based)",
+            "INDEX/TOTAL",
+        );
+        opts.optopt(
+            "",
+            "shard-timings",
+            "Balance the partitions by the execution time of tests saved with --save-timings",
+            "PATH",
+        );
+        opts.optopt(
+            "",
+            "save-timings",
+            "Save the execution time of tests to the specified file",
+            "PATH",
+        );
+        opts.optflag(
+            "",
+            "progress",
+            "Display the live status of running tests when stdout is a terminal",
+        );
+        opts.optopt(
+            "",
+            "warn-slow",
+            "Warn about tests running longer than SECS seconds, and list the running tests
+             when no test has completed for SECS seconds (default: 60, 0 to disable)",
+            "SECS",
+        );
         opts.optflag(
             "",
             "exact",
@@ -200,7 +539,7 @@ impl Parser {
             "Configure formatting of output:
                 pretty = Print verbose output;
                 terse  = Display one character per test;
-                json   = Output a json document (placeholder, not implemented yet)",
+                json   = Output a json document",
             "pretty|terse|json",
         );
 
@@ -222,7 +561,12 @@ impl Parser {
             r#"{usage}
     
     The FILTER string is tested against the name of all tests, and only those
-    tests whose names contain the filter are run."#,
+    tests whose names contain the filter are run, or whose names are equal to it
+    with --exact.
+
+    The parameterized tests are named like `base[key=value,...]`, where `,`, `=`,
+    `]` and `\\` in the keys and values are escaped with `\\`. A FILTER of the
+    same form selects the tests having all the given parameters in any order."#,
             usage = self.opts.usage(&message)
         );
     }
@@ -235,7 +579,7 @@ impl Parser {
             return Ok(None);
         }
 
-        let filter = matches.free.get(0).cloned();
+        let filter = matches.free.first().cloned();
         let run_ignored = matches.opt_present("ignored");
         let quiet = matches.opt_present("quiet");
         let filter_exact = matches.opt_present("exact");
@@ -244,26 +588,74 @@ impl Parser {
 
         let run_benchmarks = matches.opt_present("bench");
         let run_tests = !run_benchmarks || matches.opt_present("test");
+        // As well as libtest, each benchmark is run once as a test unless
+        // the benchmarks are run with `--bench`.
+        let run_benchmarks_once = !run_benchmarks;
 
         let nocapture = matches.opt_present("nocapture") || {
             std::env::var("RUST_TEST_NOCAPTURE")
                 .ok()
-                .map_or(false, |val| &val != "0")
+                .is_some_and(|val| val != "0")
         };
 
         let test_threads = matches.opt_get("test-threads")?.map(|TestThreads(n)| n);
 
+        let isolate = matches.opt_present("isolate");
+        let isolated_child = std::env::var_os(isolate::CHILD_ENV).is_some();
+
+        let shard = matches
+            .opt_get("shard")?
+            .map(|ShardArg(index, total)| (index, total));
+        let shard_timings = matches.opt_get("shard-timings")?;
+        let save_timings = matches.opt_get("save-timings")?;
+
+        let max_fail = match matches.opt_get("max-fail")? {
+            Some(MaxFail(n)) => Some(n),
+            None if matches.opt_present("fail-fast") => Some(1),
+            None => None,
+        };
+
         let color = matches.opt_get("color")?.unwrap_or(ColorConfig::Auto);
 
-        let format = matches.opt_get("format")?.unwrap_or_else(|| {
-            if quiet {
-                OutputFormat::Terse
-            } else {
-                OutputFormat::Pretty
-            }
+        let format = matches.opt_get("format")?.unwrap_or(if quiet {
+            OutputFormat::Terse
+        } else {
+            OutputFormat::Pretty
         });
 
         let skip = matches.opt_strs("skip");
+        let progress = matches.opt_present("progress");
+        let warn_slow = match parse_seconds(&matches, "warn-slow")? {
+            Some(secs) if secs == Duration::from_secs(0) => None,
+            Some(secs) => Some(secs),
+            None => Some(DEFAULT_WARN_SLOW),
+        };
+
+        let BenchOptions {
+            bench_time, warmup, ..
+        } = BenchOptions::default();
+        let bench_time = parse_seconds(&matches, "bench-time")?.unwrap_or(bench_time);
+        let warmup = parse_seconds(&matches, "warmup")?.unwrap_or(warmup);
+
+        let save_baseline = parse_baseline_name(&matches, "save-baseline")?;
+        let baseline = parse_baseline_name(&matches, "baseline")?;
+        let bench_threshold = matches
+            .opt_get("bench-threshold")?
+            .map(|Threshold(pct)| pct);
+        if bench_threshold.is_some() && baseline.is_none() {
+            return Err("--bench-threshold requires --baseline".into());
+        }
+
+        let bless = matches.opt_present("bless")
+            || std::env::var("MIMICAW_BLESS")
+                .ok()
+                .is_some_and(|val| is_enabled(&val));
+
+        let duplicates = matches
+            .opt_get("duplicates")?
+            .unwrap_or(DuplicatePolicy::Error);
+
+        let artifacts_dir = matches.opt_get("artifacts-dir")?;
 
         Ok(Some(Args {
             list,
@@ -272,12 +664,146 @@ impl Parser {
             run_ignored,
             run_tests,
             run_benchmarks,
+            run_benchmarks_once,
             logfile,
             nocapture,
             color,
             format,
             test_threads,
+            isolate,
+            max_fail,
+            shard,
+            shard_timings,
+            save_timings,
             skip,
+            progress,
+            warn_slow,
+            bench_time,
+            warmup,
+            save_baseline,
+            baseline,
+            bench_threshold,
+            bless,
+            duplicates,
+            artifacts_dir,
+            isolated_child,
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{params::Matrix, test::Test};
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        let parser = Parser {
+            args: std::iter::once("test")
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect(),
+            ..Parser::new()
+        };
+        match parser.parse_args() {
+            Ok(args) => Ok(args.expect("--help is not given")),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    #[test]
+    fn child_args() {
+        let args = parse(&[
+            "--bless",
+            "--duplicates=suffix",
+            "--artifacts-dir",
+            "out",
+            "--bench",
+            "--bench-time=0.5",
+            "--warmup=0",
+            "--format=terse",
+            "--skip",
+            "foo",
+        ])
+        .unwrap();
+
+        let desc = Test::bench("foo bar", ()).ignore(true).desc().clone();
+        let child_args: Vec<String> = args
+            .child_args(&desc)
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect();
+        let child_args: Vec<&str> = child_args.iter().map(String::as_str).collect();
+        let child = parse(&child_args).unwrap();
+
+        assert_eq!(child.filter.as_deref(), Some("foo bar"));
+        assert!(child.filter_exact);
+        assert!(child.skip.is_empty());
+        assert!(child.run_ignored);
+        assert!(child.bless);
+        assert_eq!(child.duplicates, DuplicatePolicy::Suffix);
+        assert_eq!(child.artifacts_dir, Some(PathBuf::from("out")));
+        assert!(child.run_benchmarks);
+        assert_eq!(child.bench_time, Duration::from_millis(500));
+        assert_eq!(child.warmup, Duration::from_secs(0));
+        assert_eq!(child.format, OutputFormat::Json);
+        assert_eq!(child.warn_slow, None);
+    }
+
+    #[test]
+    fn filter_params() {
+        let tests = Matrix::new("add")
+            .param("x", vec![1])
+            .param("y", vec!["a,b"])
+            .tests(|_| ());
+        let desc = tests[0].desc();
+        assert_eq!(desc.name(), "add[x=1,y=a\\,b]");
+        let matches = |args: &[&str]| !parse(args).unwrap().is_filtered(desc);
+
+        assert!(matches(&["add"]));
+        assert!(matches(&["--exact", "add[x=1,y=a\\,b]"]));
+        assert!(!matches(&["--exact", "add"]));
+
+        // The parameters may be reordered or omitted.
+        assert!(matches(&["add[y=a\\,b, x=1]"]));
+        assert!(matches(&["--exact", "add[y=a\\,b,x=1]"]));
+        assert!(matches(&["add[x=1]"]));
+        assert!(matches(&["--exact", "add[y=a\\,b]"]));
+        assert!(matches(&["ad[x=1]"]));
+        assert!(!matches(&["--exact", "ad[x=1]"]));
+
+        assert!(!matches(&["add[x=2]"]));
+        assert!(!matches(&["add[y=a]"]));
+        assert!(!matches(&["add[z=1]"]));
+    }
+
+    #[test]
+    fn enabled_by_env() {
+        assert!(is_enabled("1"));
+        assert!(is_enabled("true"));
+        assert!(is_enabled("TRUE"));
+        assert!(!is_enabled(""));
+        assert!(!is_enabled("0"));
+        assert!(!is_enabled("false"));
+        assert!(!is_enabled("yes"));
+    }
+
+    #[test]
+    fn baseline_names() {
+        let args = parse(&["--save-baseline", "main", "--baseline", "v1.0"]).unwrap();
+        assert_eq!(args.save_baseline.as_deref(), Some("main"));
+        assert_eq!(args.baseline.as_deref(), Some("v1.0"));
+
+        assert!(parse(&["--save-baseline", "../main"]).is_err());
+        assert!(parse(&["--baseline", "a/b"]).is_err());
+        assert!(parse(&["--baseline", ".."]).is_err());
+        assert!(parse(&["--baseline", ""]).is_err());
+    }
+
+    #[test]
+    fn bench_threshold_requires_baseline() {
+        assert!(parse(&["--bench-threshold", "5%"]).is_err());
+
+        let args = parse(&["--baseline", "main", "--bench-threshold", "5%"]).unwrap();
+        assert_eq!(args.bench_threshold, Some(5.0));
+    }
+}
@@ -0,0 +1,171 @@
+//! Saving the attachments of failed tests into the artifacts directory.
+
+use crate::{
+    args::Args,
+    blocking, shard, spawn, storage,
+    test::{Attachment, Outcome, OutcomeKind, TestDesc},
+};
+use futures_channel::oneshot;
+use futures_util::future::{self, FutureExt, LocalBoxFuture};
+use std::{
+    borrow::Cow,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The directory where the attachments of failed tests are saved.
+#[derive(Debug)]
+pub(crate) struct Artifacts {
+    dir: PathBuf,
+}
+
+impl Artifacts {
+    /// Use `--artifacts-dir`, or the `artifacts` directory under the data directory.
+    pub(crate) fn new(args: &Args) -> Self {
+        Self {
+            dir: args
+                .artifacts_dir
+                .clone()
+                .unwrap_or_else(|| storage::data_dir().join("artifacts")),
+        }
+    }
+
+    /// Save the attachments if the test failed, and record their paths in the outcome.
+    ///
+    /// The attachments of passed tests are discarded. The artifacts of the previous
+    /// runs are removed in either case. The files are written on a separate thread,
+    /// so that the other tests are not blocked.
+    pub(crate) fn save(
+        &self,
+        desc: &TestDesc,
+        mut outcome: Outcome,
+    ) -> LocalBoxFuture<'static, Outcome> {
+        let attachments = outcome.take_attachments();
+        if attachments.is_empty() {
+            return future::ready(outcome).boxed_local();
+        }
+
+        let dir = self.dir.join(dir_name(desc.name()));
+        blocking::run_on_thread("mimicaw-artifacts", move || {
+            save_attachments(&dir, attachments, &mut outcome);
+            outcome
+        })
+        .map(|result| match result {
+            Ok(Ok(outcome)) => outcome,
+            Ok(Err(payload)) => spawn::panicked(payload),
+            Err(oneshot::Canceled) => {
+                Outcome::failed().error_message("the thread saving the attachments was lost")
+            }
+        })
+        .boxed_local()
+    }
+}
+
+fn save_attachments(
+    dir: &Path,
+    attachments: Vec<(Cow<'static, str>, Attachment)>,
+    outcome: &mut Outcome,
+) {
+    match fs::remove_dir_all(dir) {
+        Err(ref err) if err.kind() != io::ErrorKind::NotFound => {
+            outcome.append_error_message(format!(
+                "failed to clean the artifacts directory {}: {}",
+                dir.display(),
+                err
+            ));
+            return;
+        }
+        _ => (),
+    }
+    if !matches!(outcome.kind(), OutcomeKind::Failed) {
+        return;
+    }
+
+    for (name, attachment) in attachments {
+        let path = dir.join(file_name(&name));
+        match write_attachment(&path, &attachment) {
+            Ok(()) => outcome.add_artifact(path),
+            Err(err) => outcome
+                .append_error_message(format!("failed to save the attachment `{}`: {}", name, err)),
+        }
+    }
+}
+
+fn write_attachment(path: &Path, attachment: &Attachment) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    match attachment {
+        Attachment::File(src) => fs::copy(src, path).map(drop),
+        Attachment::Bytes(bytes) => fs::write(path, bytes),
+    }
+}
+
+/// Return the name of the directory for the test.
+///
+/// The hash of the test name keeps the directories of different tests apart
+/// even if their names are sanitized into the same string.
+fn dir_name(test_name: &str) -> String {
+    format!("{}-{:08x}", sanitize(test_name), short_hash(test_name))
+}
+
+/// Return the name of the file for the attachment.
+///
+/// The names left intact by `sanitize` are used as they are, and the others get
+/// the hash of the original name before the extension, so that they do not
+/// collide with each other within the test.
+fn file_name(name: &str) -> String {
+    let sanitized = sanitize(name);
+    if sanitized == name {
+        return sanitized;
+    }
+    let hash = short_hash(name);
+    match sanitized.rfind('.').filter(|&i| i > 0) {
+        Some(i) => format!("{}-{:08x}{}", &sanitized[..i], hash, &sanitized[i..]),
+        None => format!("{}-{:08x}", sanitized, hash),
+    }
+}
+
+fn short_hash(name: &str) -> u32 {
+    shard::fnv1a(name) as u32
+}
+
+/// Replace the characters not suitable for file names with `_`.
+fn sanitize(name: &str) -> String {
+    // `.` and `..` must not refer to the directories.
+    if name.chars().all(|c| c == '.') {
+        return "_".repeat(name.len().max(1));
+    }
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' | '#' | '=' | ',' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_names() {
+        assert_eq!(sanitize("add[x=1,y=2]"), "add_x=1,y=2_");
+        assert_eq!(sanitize("dir/server.log"), "dir_server.log");
+        assert_eq!(sanitize(".."), "__");
+        assert_eq!(sanitize(""), "_");
+    }
+
+    #[test]
+    fn unique_names() {
+        assert_ne!(dir_name("a/b"), dir_name("a_b"));
+        assert_ne!(dir_name("テスト"), dir_name("試験"));
+        assert!(dir_name("a/b").starts_with("a_b-"));
+
+        assert_eq!(file_name("server.log"), "server.log");
+        assert_ne!(file_name("dir/server.log"), file_name("dir_server.log"));
+        assert!(file_name("dir/server.log").starts_with("dir_server-"));
+        assert!(file_name("dir/server.log").ends_with(".log"));
+        assert_ne!(file_name("..."), "...");
+    }
+}
@@ -0,0 +1,95 @@
+//! Integration with the async-std runtime.
+
+use crate::{
+    args::Args,
+    driver::TestRunner,
+    spawn::{self, AbortOnDrop, Spawner},
+    test::{Outcome, Test},
+};
+use ::async_std::task::{self, JoinHandle};
+use futures_core::{
+    future::Future,
+    task::{Context, Poll},
+};
+use futures_util::{future::Aborted, ready};
+use std::pin::Pin;
+
+/// A `Spawner` that spawns the test cases onto the async-std runtime.
+#[derive(Debug, Default, Clone)]
+pub struct AsyncStdSpawner {
+    _p: (),
+}
+
+impl AsyncStdSpawner {
+    /// Create a new `AsyncStdSpawner`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Spawner for AsyncStdSpawner {
+    type Handle = Handle;
+
+    fn spawn<F>(&self, future: F) -> Self::Handle
+    where
+        F: Future<Output = Outcome> + Send + 'static,
+    {
+        let (future, abort) = spawn::abort_on_drop(future);
+        Handle {
+            join: task::spawn(future),
+            _abort: abort,
+        }
+    }
+}
+
+/// The handle of a test case spawned by `AsyncStdSpawner`.
+///
+/// The test case is aborted when the handle is dropped before completion.
+#[derive(Debug)]
+pub struct Handle {
+    join: JoinHandle<Result<Outcome, Aborted>>,
+    _abort: AbortOnDrop,
+}
+
+impl Future for Handle {
+    type Output = Outcome;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let result = ready!(Pin::new(&mut self.join).poll(cx));
+        Poll::Ready(
+            result.unwrap_or_else(|Aborted| {
+                Outcome::failed().error_message("the test task was aborted")
+            }),
+        )
+    }
+}
+
+/// Run a test suite on the async-std runtime and terminate the process.
+pub fn main<D>(tests: impl IntoIterator<Item = Test<D>>, runner: impl TestRunner<D>) -> ! {
+    let args = Args::from_env().unwrap_or_else(|st| st.exit());
+    task::block_on(crate::run_tests(&args, tests, runner)).exit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_channel::oneshot;
+    use futures_util::future;
+    use std::time::Duration;
+
+    #[test]
+    fn dropped_handle_aborts_test_case() {
+        task::block_on(async {
+            let (tx, rx) = oneshot::channel::<()>();
+            let handle = AsyncStdSpawner::new().spawn(async move {
+                // The sender is dropped together with the test case.
+                let _tx = tx;
+                future::pending().await
+            });
+            drop(handle);
+
+            let stopped = ::async_std::future::timeout(Duration::from_secs(10), rx).await;
+            assert!(stopped.is_ok(), "the test case is still running");
+        });
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{bench::BenchStats, storage, test::TestDesc};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+/// The comparison of a benchmark result with the saved baseline.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct BenchChange {
+    /// The median of the baseline, in nanoseconds per iteration.
+    pub baseline: u64,
+
+    /// The median of the current result, in nanoseconds per iteration.
+    pub current: u64,
+
+    /// The relative change from the baseline, in percent.
+    pub percent: f64,
+
+    /// Whether the change exceeds the threshold given by `--bench-threshold`.
+    pub regressed: bool,
+}
+
+/// The benchmark results saved with `--save-baseline`.
+///
+/// Each line of the file consists of the benchmark name and the median
+/// separated by a tab character.
+#[derive(Debug, Default)]
+pub(crate) struct Baseline {
+    medians: BTreeMap<String, u64>,
+}
+
+impl Baseline {
+    fn path(name: &str) -> PathBuf {
+        storage::data_dir().join("baselines").join(name)
+    }
+
+    pub(crate) fn load(name: &str) -> io::Result<Self> {
+        let content = fs::read_to_string(Self::path(name))?;
+        let medians = content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.rsplitn(2, '\t');
+                let median = fields.next()?.parse().ok()?;
+                let name = fields.next()?;
+                Some((name.to_owned(), median))
+            })
+            .collect();
+        Ok(Self { medians })
+    }
+
+    /// Update the baseline with the benchmark results and save it.
+    ///
+    /// The results of benchmarks that did not run this time are preserved.
+    pub(crate) fn save<'a>(
+        name: &str,
+        measured: impl IntoIterator<Item = &'a (TestDesc, BenchStats)>,
+    ) -> io::Result<()> {
+        let mut baseline = match Self::load(name) {
+            Ok(baseline) => baseline,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(err) => return Err(err),
+        };
+        for (desc, stats) in measured {
+            baseline
+                .medians
+                .insert(desc.name().to_owned(), stats.median);
+        }
+
+        let path = Self::path(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        for (name, median) in &baseline.medians {
+            writeln!(file, "{}\t{}", name, median)?;
+        }
+        file.flush()
+    }
+
+    pub(crate) fn compare(
+        &self,
+        name: &str,
+        stats: &BenchStats,
+        threshold: Option<f64>,
+    ) -> Option<BenchChange> {
+        let baseline = *self.medians.get(name)?;
+        let percent = if baseline == 0 {
+            0.0
+        } else {
+            (stats.median as f64 - baseline as f64) / baseline as f64 * 100.0
+        };
+        Some(BenchChange {
+            baseline,
+            current: stats.median,
+            percent,
+            regressed: threshold.is_some_and(|threshold| percent > threshold),
+        })
+    }
+}
@@ -0,0 +1,370 @@
+use crate::test::Outcome;
+use futures_core::future::Future;
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+/// The number of samples collected for each benchmark.
+const NUM_SAMPLES: usize = 50;
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct BenchOptions {
+    pub(crate) bench_time: Duration,
+    pub(crate) warmup: Duration,
+    pub(crate) smoke_test: bool,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            bench_time: Duration::from_secs(3),
+            warmup: Duration::from_secs(1),
+            smoke_test: false,
+        }
+    }
+}
+
+/// A timer for measuring the performance of benchmark tests.
+///
+/// The instance of `Bencher` is obtained from [`TestDesc::bencher`],
+/// and it is configured by the command line options `--bench-time`
+/// and `--warmup`.
+///
+/// If the benchmark is run as a smoke test (see [`TestDesc::is_smoke_test`]),
+/// the measurement is skipped and the benchmark is executed only once.
+///
+/// [`TestDesc::bencher`]: ./struct.TestDesc.html#method.bencher
+/// [`TestDesc::is_smoke_test`]: ./struct.TestDesc.html#method.is_smoke_test
+#[derive(Debug)]
+pub struct Bencher {
+    options: BenchOptions,
+}
+
+impl Bencher {
+    pub(crate) fn new(options: BenchOptions) -> Self {
+        Self { options }
+    }
+
+    /// Measure the execution time of the specified closure.
+    ///
+    /// The closure is repeatedly called during the warm-up period,
+    /// and then the number of iterations per sample is chosen so that
+    /// all samples fit in the measurement time.
+    ///
+    /// When the benchmark is run as a smoke test, the closure is called
+    /// only once and the passed outcome is returned.
+    pub fn iter<T>(&mut self, mut f: impl FnMut() -> T) -> Outcome {
+        if self.options.smoke_test {
+            black_box(f());
+            return Outcome::passed();
+        }
+
+        let mut sampler = Sampler::new(self.options);
+        while let Some(n) = sampler.next_batch() {
+            let start = Instant::now();
+            for _ in 0..n {
+                black_box(f());
+            }
+            sampler.record(n, start.elapsed());
+        }
+        sampler.finish()
+    }
+
+    /// Measure the execution time of the futures returned from the specified closure.
+    ///
+    /// This method is the asynchronous version of [`iter`](#method.iter).
+    pub async fn iter_async<F, Fut>(&mut self, mut f: F) -> Outcome
+    where
+        F: FnMut() -> Fut,
+        Fut: Future,
+    {
+        if self.options.smoke_test {
+            black_box(f().await);
+            return Outcome::passed();
+        }
+
+        let mut sampler = Sampler::new(self.options);
+        while let Some(n) = sampler.next_batch() {
+            let start = Instant::now();
+            for _ in 0..n {
+                black_box(f().await);
+            }
+            sampler.record(n, start.elapsed());
+        }
+        sampler.finish()
+    }
+}
+
+#[derive(Debug)]
+enum SamplerState {
+    Warmup {
+        batch_size: u64,
+        iterations: u64,
+        elapsed: Duration,
+    },
+    Measure {
+        batch_size: u64,
+    },
+}
+
+/// The state machine that decides the number of iterations of each batch.
+#[derive(Debug)]
+struct Sampler {
+    options: BenchOptions,
+    state: SamplerState,
+    samples: Vec<f64>,
+}
+
+impl Sampler {
+    fn new(options: BenchOptions) -> Self {
+        Self {
+            options,
+            state: SamplerState::Warmup {
+                batch_size: 1,
+                iterations: 0,
+                elapsed: Duration::from_secs(0),
+            },
+            samples: Vec::with_capacity(NUM_SAMPLES),
+        }
+    }
+
+    fn next_batch(&self) -> Option<u64> {
+        match self.state {
+            SamplerState::Warmup { batch_size, .. } => Some(batch_size),
+            SamplerState::Measure { .. } if self.samples.len() >= NUM_SAMPLES => None,
+            SamplerState::Measure { batch_size } => Some(batch_size),
+        }
+    }
+
+    fn record(&mut self, n: u64, batch_elapsed: Duration) {
+        match self.state {
+            SamplerState::Warmup {
+                ref mut batch_size,
+                ref mut iterations,
+                ref mut elapsed,
+            } => {
+                *iterations += n;
+                *elapsed += batch_elapsed;
+                if *elapsed < self.options.warmup {
+                    *batch_size = batch_size.saturating_mul(2);
+                    return;
+                }
+
+                // Choose the batch size from the average time per iteration
+                // observed during the warm-up period.
+                let ns_per_iter = (elapsed.as_nanos() as f64 / *iterations as f64).max(1.0);
+                let ns_per_sample = self.options.bench_time.as_nanos() as f64 / NUM_SAMPLES as f64;
+                let batch_size = ((ns_per_sample / ns_per_iter) as u64).max(1);
+                self.state = SamplerState::Measure { batch_size };
+            }
+            SamplerState::Measure { .. } => {
+                self.samples
+                    .push(batch_elapsed.as_nanos() as f64 / n as f64);
+            }
+        }
+    }
+
+    fn finish(self) -> Outcome {
+        Outcome::measured_with_stats(BenchStats::from_samples(&self.samples))
+    }
+}
+
+/// Statistics of the benchmark samples.
+///
+/// All values except the number of samples and outliers are
+/// in nanoseconds per iteration.
+///
+/// If the benchmark only reported the average by `Outcome::measured`,
+/// `samples` is zero, and only `average` and `variance` are meaningful.
+/// The other values are filled with the average, and are neither displayed
+/// nor written to the JSON output.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct BenchStats {
+    /// The number of collected samples.
+    pub samples: usize,
+
+    /// The arithmetic mean of samples.
+    pub average: u64,
+
+    /// The spread of samples, that is the distance between
+    /// the 5th and 95th percentiles.
+    pub variance: u64,
+
+    /// The minimum of samples.
+    pub min: u64,
+
+    /// The maximum of samples.
+    pub max: u64,
+
+    /// The median of samples.
+    pub median: u64,
+
+    /// The 25th percentile of samples.
+    pub p25: u64,
+
+    /// The 75th percentile of samples.
+    pub p75: u64,
+
+    /// The 90th percentile of samples.
+    pub p90: u64,
+
+    /// The 99th percentile of samples.
+    pub p99: u64,
+
+    /// The number of samples below the lower Tukey's fence.
+    pub outliers_low: usize,
+
+    /// The number of samples above the upper Tukey's fence.
+    pub outliers_high: usize,
+}
+
+impl BenchStats {
+    /// Compute the statistics from the samples, in nanoseconds per iteration.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `samples` is empty or contains NaN.
+    pub fn from_samples(samples: &[f64]) -> Self {
+        assert!(!samples.is_empty(), "no benchmark samples");
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).expect("NaN in samples"));
+        let pct = |p| percentile_of_sorted(&sorted, p);
+
+        let p25 = pct(25.0);
+        let p75 = pct(75.0);
+        let iqr = p75 - p25;
+        let (lo_fence, hi_fence) = (p25 - 1.5 * iqr, p75 + 1.5 * iqr);
+
+        Self {
+            samples: sorted.len(),
+            average: (sorted.iter().sum::<f64>() / sorted.len() as f64) as u64,
+            variance: (pct(95.0) - pct(5.0)) as u64,
+            min: sorted[0] as u64,
+            max: sorted[sorted.len() - 1] as u64,
+            median: pct(50.0) as u64,
+            p25: p25 as u64,
+            p75: p75 as u64,
+            p90: pct(90.0) as u64,
+            p99: pct(99.0) as u64,
+            outliers_low: sorted.iter().filter(|&&x| x < lo_fence).count(),
+            outliers_high: sorted.iter().filter(|&&x| x > hi_fence).count(),
+        }
+    }
+
+    /// Create the statistics that only knows the average and variance, with no samples.
+    pub(crate) fn from_average(average: u64, variance: u64) -> Self {
+        Self {
+            samples: 0,
+            average,
+            variance,
+            min: average,
+            max: average,
+            median: average,
+            p25: average,
+            p75: average,
+            p90: average,
+            p99: average,
+            outliers_low: 0,
+            outliers_high: 0,
+        }
+    }
+}
+
+fn percentile_of_sorted(sorted: &[f64], pct: f64) -> f64 {
+    debug_assert!(!sorted.is_empty());
+    if sorted.len() == 1 {
+        return sorted[0];
+    }
+    let rank = (pct / 100.0) * (sorted.len() - 1) as f64;
+    let lrank = rank.floor();
+    let n = lrank as usize;
+    match sorted.get(n + 1) {
+        Some(&hi) => sorted[n] + (hi - sorted[n]) * (rank - lrank),
+        None => sorted[n],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampler_warmup_doubles_batch_size() {
+        let mut sampler = Sampler::new(BenchOptions {
+            bench_time: Duration::from_millis(50),
+            warmup: Duration::from_millis(100),
+            smoke_test: false,
+        });
+
+        // Each iteration takes 10 us during the warm-up.
+        let mut total = 0;
+        for expected in [
+            1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096, 8192,
+        ] {
+            assert_eq!(sampler.next_batch(), Some(expected));
+            sampler.record(expected, Duration::from_micros(10 * expected));
+            total += expected;
+            if total * 10 >= 100_000 {
+                break;
+            }
+        }
+        assert!(matches!(sampler.state, SamplerState::Measure { .. }));
+
+        // 1 ms per sample fits 100 iterations of 10 us.
+        for _ in 0..NUM_SAMPLES {
+            assert_eq!(sampler.next_batch(), Some(100));
+            sampler.record(100, Duration::from_millis(1));
+        }
+        assert_eq!(sampler.next_batch(), None);
+        assert_eq!(sampler.samples, vec![10_000.0; NUM_SAMPLES]);
+    }
+
+    #[test]
+    fn sampler_batch_size_is_positive() {
+        let mut sampler = Sampler::new(BenchOptions {
+            bench_time: Duration::from_nanos(1),
+            warmup: Duration::from_nanos(1),
+            smoke_test: false,
+        });
+        assert_eq!(sampler.next_batch(), Some(1));
+        sampler.record(1, Duration::from_secs(1));
+        assert_eq!(sampler.next_batch(), Some(1));
+    }
+
+    #[test]
+    fn percentiles() {
+        let sorted = [10.0, 20.0, 30.0, 40.0, 50.0];
+        assert_eq!(percentile_of_sorted(&sorted, 0.0), 10.0);
+        assert_eq!(percentile_of_sorted(&sorted, 50.0), 30.0);
+        assert_eq!(percentile_of_sorted(&sorted, 100.0), 50.0);
+        // Interpolated between the neighbors.
+        assert_eq!(percentile_of_sorted(&sorted, 10.0), 14.0);
+        assert_eq!(percentile_of_sorted(&sorted, 90.0), 46.0);
+        assert_eq!(percentile_of_sorted(&[42.0], 75.0), 42.0);
+    }
+
+    #[test]
+    fn stats_from_samples() {
+        let samples: Vec<f64> = (1..=100).map(f64::from).chain(Some(1000.0)).collect();
+        let stats = BenchStats::from_samples(&samples);
+        assert_eq!(stats.samples, 101);
+        assert_eq!(stats.min, 1);
+        assert_eq!(stats.max, 1000);
+        assert_eq!(stats.median, 51);
+        assert_eq!(stats.p25, 26);
+        assert_eq!(stats.p75, 76);
+        assert_eq!(stats.outliers_low, 0);
+        assert_eq!(stats.outliers_high, 1);
+    }
+
+    #[test]
+    fn stats_from_single_sample() {
+        let stats = BenchStats::from_samples(&[42.0]);
+        assert_eq!(stats.average, 42);
+        assert_eq!(stats.variance, 0);
+        assert_eq!(stats.p99, 42);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{driver::TestRunner, spawn, test::Outcome, TestDesc};
+use futures_channel::oneshot;
+use futures_core::{
+    future::Future,
+    task::{self, Poll},
+};
+use futures_util::ready;
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The worker threads running the synchronous test bodies.
+///
+/// The threads are spawned at the first job, and exit when the pool is dropped.
+#[derive(Clone)]
+pub(crate) struct Pool {
+    num_threads: usize,
+    tx: Option<mpsc::Sender<Job>>,
+}
+
+impl Default for Pool {
+    fn default() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+}
+
+impl Pool {
+    pub(crate) fn new(num_threads: usize) -> Self {
+        Self {
+            num_threads,
+            tx: None,
+        }
+    }
+
+    /// Call `f` on a worker thread, catching the panic in it.
+    ///
+    /// The call is skipped if the receiver is dropped while waiting for a free thread.
+    pub(crate) fn run<F, T>(&mut self, f: F) -> oneshot::Receiver<thread::Result<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job = Box::new(move || {
+            if tx.is_canceled() {
+                return;
+            }
+            let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(f)));
+        });
+        // The receiver is alive as long as the senders exist.
+        let _ = self.sender().send(job);
+        rx
+    }
+
+    /// Return the sender of jobs, spawning the worker threads at the first call.
+    fn sender(&mut self) -> &mpsc::Sender<Job> {
+        let num_threads = self.num_threads;
+        self.tx.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::channel::<Job>();
+            let rx = Arc::new(Mutex::new(rx));
+            for i in 0..num_threads {
+                let rx = rx.clone();
+                thread::Builder::new()
+                    .name(format!("mimicaw-blocking-{}", i))
+                    .spawn(move || loop {
+                        let job = match rx.lock().unwrap_or_else(|e| e.into_inner()).recv() {
+                            Ok(job) => job,
+                            Err(..) => break,
+                        };
+                        job();
+                    })
+                    .expect("failed to spawn the worker thread");
+            }
+            tx
+        })
+    }
+}
+
+/// Call `f` on a new thread named `name`, catching the panic in it.
+///
+/// Unlike `Pool::run`, the call starts immediately, and the thread is not
+/// shared with the other calls even if `f` never returns.
+pub(crate) fn run_on_thread<F, T>(name: &str, f: F) -> oneshot::Receiver<thread::Result<T>>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    thread::Builder::new()
+        .name(name.into())
+        .spawn(move || {
+            let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(f)));
+        })
+        .expect("failed to spawn the thread");
+    rx
+}
+
+/// A test runner that runs synchronous test bodies on a dedicated thread pool.
+///
+/// Since the test bodies do not block the task awaiting `run_tests`,
+/// they can be mixed with asynchronous test cases without starving them.
+/// The panics in the test bodies are caught and reported as failures.
+pub struct BlockingRunner<F> {
+    runner: Arc<F>,
+    pool: Pool,
+}
+
+impl<F> fmt::Debug for BlockingRunner<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockingRunner")
+            .field("num_threads", &self.pool.num_threads)
+            .finish()
+    }
+}
+
+impl<F> BlockingRunner<F> {
+    /// Create a test runner that calls `runner` on the thread pool.
+    ///
+    /// The number of threads defaults to the available parallelism.
+    pub fn new(runner: F) -> Self {
+        Self {
+            runner: Arc::new(runner),
+            pool: Pool::default(),
+        }
+    }
+
+    /// Specify the number of threads running the test bodies.
+    pub fn num_threads(mut self, num_threads: usize) -> Self {
+        assert!(num_threads > 0, "the number of threads must be positive");
+        self.pool = Pool::new(num_threads);
+        self
+    }
+}
+
+impl<D, F> TestRunner<D> for BlockingRunner<F>
+where
+    F: Fn(TestDesc, D) -> Outcome + Send + Sync + 'static,
+    D: Send + 'static,
+{
+    type Future = BlockingTest;
+
+    fn run(&mut self, desc: TestDesc, data: D) -> Self::Future {
+        let runner = self.runner.clone();
+        BlockingTest {
+            rx: self.pool.run(move || runner(desc, data)),
+        }
+    }
+}
+
+/// The future returned from `BlockingRunner`, resolved to the outcome of the test body.
+#[derive(Debug)]
+pub struct BlockingTest {
+    rx: oneshot::Receiver<thread::Result<Outcome>>,
+}
+
+impl Future for BlockingTest {
+    type Output = Outcome;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let outcome = ready!(Pin::new(&mut self.rx).poll(cx));
+        Poll::Ready(match outcome {
+            Ok(Ok(outcome)) => outcome,
+            Ok(Err(payload)) => spawn::panicked(payload),
+            Err(oneshot::Canceled) => {
+                Outcome::failed().error_message("the worker thread was lost unexpectedly")
+            }
+        })
+    }
+}
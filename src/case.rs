@@ -0,0 +1,212 @@
+use crate::{
+    blocking::{self, Pool},
+    driver::TestRunner,
+    spawn,
+    test::Outcome,
+    TestDesc,
+};
+use futures_channel::oneshot;
+use futures_core::future::Future;
+use futures_timer::Delay;
+use futures_util::{
+    future::{self, Either, FutureExt, LocalBoxFuture},
+    pin_mut,
+};
+use std::{borrow::Cow, fmt, panic::AssertUnwindSafe, time::Duration};
+
+enum Body {
+    Async(Box<dyn FnOnce(TestDesc) -> LocalBoxFuture<'static, Outcome>>),
+    Blocking(Box<dyn FnOnce(TestDesc) -> Outcome + Send>),
+}
+
+/// A test case executed by `DefaultRunner`.
+///
+/// This is the type of test data generated by `#[mimicaw::test]`.
+pub struct TestCase {
+    body: Body,
+    timeout: Option<Duration>,
+    should_panic: Option<Option<Cow<'static, str>>>,
+}
+
+impl fmt::Debug for TestCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestCase")
+            .field("timeout", &self.timeout)
+            .field("should_panic", &self.should_panic)
+            .finish()
+    }
+}
+
+impl TestCase {
+    fn new(body: Body) -> Self {
+        Self {
+            body,
+            timeout: None,
+            should_panic: None,
+        }
+    }
+
+    /// Create a test case from the asynchronous test body.
+    pub fn from_async<F, Fut>(f: F) -> Self
+    where
+        F: FnOnce(TestDesc) -> Fut + 'static,
+        Fut: Future<Output = Outcome> + 'static,
+    {
+        Self::new(Body::Async(Box::new(move |desc| f(desc).boxed_local())))
+    }
+
+    /// Create a test case from the synchronous test body.
+    ///
+    /// The test body is run on a thread pool shared by the synchronous test bodies,
+    /// so that it does not block the other test cases. If a timeout is specified,
+    /// the test body is run on its own thread instead, so that the time waiting for
+    /// a free thread is not counted and a hung test body does not occupy the pool.
+    pub fn from_blocking<F>(f: F) -> Self
+    where
+        F: FnOnce(TestDesc) -> Outcome + Send + 'static,
+    {
+        Self::new(Body::Blocking(Box::new(f)))
+    }
+
+    /// Fail the test case if it does not complete within the specified duration.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Mark that the test body should panic.
+    pub fn should_panic(mut self) -> Self {
+        self.should_panic = Some(None);
+        self
+    }
+
+    /// Mark that the test body should panic with the message containing `expected`.
+    pub fn should_panic_with(mut self, expected: impl Into<Cow<'static, str>>) -> Self {
+        self.should_panic = Some(Some(expected.into()));
+        self
+    }
+}
+
+/// The test runner that executes `TestCase`s.
+///
+/// The panics in the test bodies are caught and reported as failures,
+/// unless the test case is marked as `should_panic`.
+#[derive(Default, Clone)]
+pub struct DefaultRunner {
+    pool: Pool,
+}
+
+impl fmt::Debug for DefaultRunner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DefaultRunner").finish()
+    }
+}
+
+impl DefaultRunner {
+    /// Create a new `DefaultRunner`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TestRunner<TestCase> for DefaultRunner {
+    type Future = LocalBoxFuture<'static, Outcome>;
+
+    fn run(&mut self, desc: TestDesc, case: TestCase) -> Self::Future {
+        let TestCase {
+            body,
+            timeout,
+            should_panic,
+        } = case;
+
+        let test_case = match body {
+            Body::Async(f) => AssertUnwindSafe(async move { f(desc).await })
+                .catch_unwind()
+                .boxed_local(),
+            Body::Blocking(f) => match timeout {
+                Some(..) => blocking::run_on_thread("mimicaw-timed", move || f(desc)),
+                None => self.pool.run(move || f(desc)),
+            }
+            .map(|result| {
+                result.unwrap_or_else(|oneshot::Canceled| {
+                    Err(Box::new("the worker thread was lost unexpectedly"))
+                })
+            })
+            .boxed_local(),
+        };
+
+        async move {
+            let result = match timeout {
+                Some(timeout) => {
+                    pin_mut!(test_case);
+                    match future::select(test_case, Delay::new(timeout)).await {
+                        Either::Left((result, _)) => result,
+                        Either::Right(..) => {
+                            return Outcome::failed().error_message(format!(
+                                "the test case timed out after {:?}",
+                                timeout
+                            ))
+                        }
+                    }
+                }
+                None => test_case.await,
+            };
+
+            match (result, should_panic) {
+                (Ok(outcome), None) => outcome,
+                (Err(payload), None) => spawn::panicked(payload),
+                (Ok(..), Some(..)) => {
+                    Outcome::failed().error_message("the test case did not panic as expected")
+                }
+                (Err(..), Some(None)) => Outcome::passed(),
+                (Err(payload), Some(Some(expected))) => {
+                    let msg = spawn::panic_message(&*payload);
+                    if msg.contains(&*expected) {
+                        Outcome::passed()
+                    } else {
+                        Outcome::failed().error_message(format!(
+                            "the panic message did not contain the expected string\n  \
+                             panic message: {:?}\n expected string: {:?}",
+                            msg, expected
+                        ))
+                    }
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{OutcomeKind, Test};
+    use futures::executor::block_on;
+    use std::thread;
+
+    #[test]
+    fn timeout_excludes_waiting_for_pool() {
+        let mut runner = DefaultRunner { pool: Pool::new(1) };
+
+        // The only worker thread of the pool is occupied by the untimed body.
+        let busy = runner.run(
+            Test::test("busy", ()).desc().clone(),
+            TestCase::from_blocking(|_| {
+                thread::sleep(Duration::from_millis(500));
+                Outcome::passed()
+            }),
+        );
+        let timed = runner.run(
+            Test::test("timed", ()).desc().clone(),
+            TestCase::from_blocking(|_| Outcome::passed()).timeout(Duration::from_millis(100)),
+        );
+
+        let timed = block_on(timed);
+        assert!(
+            matches!(timed.kind(), OutcomeKind::Passed),
+            "{:?}",
+            timed.err_msg()
+        );
+        assert!(matches!(block_on(busy).kind(), OutcomeKind::Passed));
+    }
+}
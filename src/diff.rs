@@ -0,0 +1,141 @@
+//! A line-based diff used for reporting mismatched values.
+
+/// The number of unchanged lines displayed around the changes.
+const CONTEXT_LINES: usize = 3;
+
+/// The maximum size of the table used for computing the diff.
+/// Larger inputs are reported as replaced entirely.
+const MAX_TABLE_SIZE: usize = 4_000_000;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Compute the diff from `expected` to `actual`, line by line.
+pub(crate) fn diff_lines<'a>(expected: &'a str, actual: &'a str) -> Vec<Line<'a>> {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut lines: Vec<Line<'a>> = old[..prefix].iter().map(|&l| Line::Same(l)).collect();
+    if (old_mid.len() + 1) * (new_mid.len() + 1) > MAX_TABLE_SIZE {
+        lines.extend(old_mid.iter().map(|&l| Line::Removed(l)));
+        lines.extend(new_mid.iter().map(|&l| Line::Added(l)));
+    } else {
+        lines.extend(lcs_diff(old_mid, new_mid));
+    }
+    lines.extend(old[old.len() - suffix..].iter().map(|&l| Line::Same(l)));
+    lines
+}
+
+fn lcs_diff<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    // table[i][j] is the length of LCS of old[i..] and new[j..].
+    let width = new.len() + 1;
+    let mut table = vec![0usize; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            table[i * width + j] = if old[i] == new[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            lines.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|&l| Line::Removed(l)));
+    lines.extend(new[j..].iter().map(|&l| Line::Added(l)));
+    lines
+}
+
+/// Compute the diff, omitting the unchanged lines far from the changes.
+///
+/// Each run of the omitted lines is replaced with `None`.
+pub(crate) fn diff_with_context<'a>(expected: &'a str, actual: &'a str) -> Vec<Option<Line<'a>>> {
+    let lines = diff_lines(expected, actual);
+    let changed: Vec<bool> = lines
+        .iter()
+        .map(|line| !matches!(line, Line::Same(..)))
+        .collect();
+    let near_change = |i: usize| {
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let end = (i + CONTEXT_LINES + 1).min(lines.len());
+        changed[start..end].iter().any(|&c| c)
+    };
+
+    let mut out = vec![];
+    for (i, &line) in lines.iter().enumerate() {
+        if near_change(i) {
+            out.push(Some(line));
+        } else if out.last() != Some(&None) {
+            out.push(None);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_changed_line() {
+        assert_eq!(
+            diff_lines("a\nb\nc\n", "a\nx\nc\nd\n"),
+            vec![
+                Line::Same("a"),
+                Line::Removed("b"),
+                Line::Added("x"),
+                Line::Same("c"),
+                Line::Added("d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn omit_far_from_changes() {
+        let expected: String = (0..20).map(|i| format!("{}\n", i)).collect();
+        let actual = expected.replace("10\n", "ten\n");
+        assert_eq!(
+            diff_with_context(&expected, &actual),
+            vec![
+                None,
+                Some(Line::Same("7")),
+                Some(Line::Same("8")),
+                Some(Line::Same("9")),
+                Some(Line::Removed("10")),
+                Some(Line::Added("ten")),
+                Some(Line::Same("11")),
+                Some(Line::Same("12")),
+                Some(Line::Same("13")),
+                None,
+            ]
+        );
+    }
+}
@@ -1,7 +1,16 @@
 use crate::{
-    args::Args,
+    args::{Args, DuplicatePolicy},
+    artifacts::Artifacts,
+    baseline::Baseline,
+    duplicates, interrupt,
+    isolate::ChildReporter,
+    isolate::Isolation,
     printer::Printer,
-    report::Report,
+    progress::Progress,
+    report::{Failure, Report, StopReason},
+    reporter::{self, Reporter},
+    shard::{self, Shard},
+    snapshot::SnapshotOptions,
     test::{Outcome, OutcomeKind, Test, TestDesc, TestKind},
     ExitStatus,
 };
@@ -9,9 +18,14 @@ use futures_core::{
     future::Future,
     task::{self, Poll},
 };
-use futures_util::{ready, stream::StreamExt};
+use futures_timer::Delay;
+use futures_util::{
+    future::{self, Either, LocalBoxFuture},
+    pin_mut, ready,
+    stream::StreamExt,
+};
 use pin_project::pin_project;
-use std::{collections::HashSet, io::Write, pin::Pin};
+use std::{pin::Pin, rc::Rc, time::Duration};
 
 /// The runner of test cases.
 pub trait TestRunner<D> {
@@ -40,25 +54,29 @@ struct PendingTest<'a, D, R> {
     context: Option<D>,
     #[pin]
     test_case: Option<R>,
+    saving: Option<LocalBoxFuture<'static, Outcome>>,
     outcome: Option<Outcome>,
-    printer: &'a Printer,
-    name_length: usize,
+    elapsed: Option<Duration>,
+    started: bool,
+    cancelled: bool,
+    progress: &'a Progress<'a>,
+    artifacts: &'a Artifacts,
 }
 
 impl<D, R> PendingTest<'_, D, R> {
-    fn start<F>(self: Pin<&mut Self>, args: &Args, name_length: usize, runner: &mut F)
+    fn start<F>(self: Pin<&mut Self>, args: &Args, runner: &mut F)
     where
         F: TestRunner<D, Future = R>,
         R: Future<Output = Outcome>,
     {
         let mut me = self.project();
 
-        *me.name_length = name_length;
+        *me.started = true;
 
         let ignored = (me.desc.ignored() && !args.run_ignored)
             || match me.desc.kind() {
                 TestKind::Test => !args.run_tests,
-                TestKind::Bench => !args.run_benchmarks,
+                TestKind::Bench => !args.run_benchmarks && !args.run_benchmarks_once,
             };
 
         let context = me
@@ -69,6 +87,18 @@ impl<D, R> PendingTest<'_, D, R> {
         if !ignored {
             let test_case = runner.run(me.desc.clone(), context);
             me.test_case.set(Some(test_case));
+            me.progress.test_started(me.desc);
+        }
+    }
+
+    /// Drop the running test case without waiting for its completion.
+    ///
+    /// The test case is not cancelled once its attachments are being saved.
+    fn cancel(self: Pin<&mut Self>) {
+        let mut me = self.project();
+        if me.outcome.is_none() && me.saving.is_none() && me.test_case.is_some() {
+            me.test_case.set(None);
+            *me.cancelled = true;
         }
     }
 }
@@ -82,71 +112,207 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
         let me = self.project();
 
-        match me.test_case.as_pin_mut() {
-            Some(test_case) => {
-                let outcome = ready!(test_case.poll(cx));
-                me.printer
-                    .print_result(me.desc, *me.name_length, Some(&outcome));
-                me.outcome.replace(outcome);
-            }
-            None => {
-                me.printer.print_result(me.desc, *me.name_length, None);
+        if me.saving.is_none() {
+            match me.test_case.as_pin_mut() {
+                Some(test_case) => {
+                    let mut outcome = ready!(test_case.poll(cx));
+                    if me.desc.is_smoke_test() {
+                        outcome = outcome.into_smoke_result();
+                    }
+                    me.saving.replace(me.artifacts.save(me.desc, outcome));
+                }
+                None => {
+                    me.progress.test_finished(me.desc, None);
+                    return Poll::Ready(());
+                }
             }
         }
 
+        let saving = me.saving.as_mut().expect("the attachments are being saved");
+        let outcome = ready!(saving.as_mut().poll(cx));
+        *me.elapsed = me.progress.test_finished(me.desc, Some(&outcome));
+        me.outcome.replace(outcome);
+
         Poll::Ready(())
     }
 }
 
 pub(crate) struct TestDriver<'a> {
     args: &'a Args,
-    printer: Printer,
+    printer: Rc<Printer>,
 }
 
 impl<'a> TestDriver<'a> {
     pub(crate) fn new(args: &'a Args) -> Self {
-        let printer = Printer::new(&args);
+        let printer = Rc::new(Printer::new(args.color, args.format));
         Self { args, printer }
     }
 
+    fn shard(&self, progress: &Progress<'_>) -> Result<Option<Shard>, ExitStatus> {
+        let (index, total) = match self.args.shard {
+            Some(shard) => shard,
+            None => return Ok(None),
+        };
+        let shard = Shard::new(index, total);
+        match self.args.shard_timings {
+            Some(ref path) => match shard.with_timings(path) {
+                Ok(shard) => Ok(Some(shard)),
+                Err(err) => {
+                    // Falling back to another partitioning may cause some tests
+                    // to be run by multiple shards or none of them.
+                    progress.error(format_args!(
+                        "failed to load the timings from {}: {}",
+                        path.display(),
+                        err
+                    ));
+                    Err(ExitStatus::FAILED)
+                }
+            },
+            None => Ok(Some(shard)),
+        }
+    }
+
+    /// Handle the tests sharing the same name according to `--duplicates`.
+    fn resolve_duplicates<D>(
+        &self,
+        tests: &mut [Test<D>],
+        progress: &Progress<'_>,
+    ) -> Result<(), ExitStatus> {
+        let names: Vec<&str> = tests.iter().map(|test| test.desc().name()).collect();
+        match self.args.duplicates {
+            DuplicatePolicy::Error => {
+                let duplicates = duplicates::find_duplicates(&names);
+                if duplicates.is_empty() {
+                    return Ok(());
+                }
+                let mut msg = format!(
+                    "found {} duplicated test name(s) (use --duplicates=suffix to rename them):",
+                    duplicates.len()
+                );
+                for (name, positions) in duplicates {
+                    let positions: Vec<String> = positions.iter().map(|i| i.to_string()).collect();
+                    msg += &format!("\n    {} (at positions {})", name, positions.join(", "));
+                }
+                progress.error(msg);
+                Err(ExitStatus::FAILED)
+            }
+            DuplicatePolicy::Suffix => {
+                let renamed = duplicates::disambiguate(&names);
+                for (test, name) in tests.iter_mut().zip(renamed) {
+                    if let Some(name) = name {
+                        if !self.args.isolated_child {
+                            progress.warning(format_args!(
+                                "renamed the duplicated test {} to {}",
+                                test.desc().name(),
+                                name
+                            ));
+                        }
+                        test.rename(name);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Run the tests, reporting the results to the reporter selected by `--format`
+    /// if `reporter` is `None`.
     pub(crate) async fn run_tests<D>(
         &self,
         tests: impl IntoIterator<Item = Test<D>>,
         runner: impl TestRunner<D>,
+        reporter: Option<&mut dyn Reporter>,
     ) -> Result<Report, ExitStatus> {
         let mut runner = runner;
+        // The child process must report the outcome to the parent in any case.
+        let mut child_reporter;
+        let mut default_reporter;
+        let allow_live = reporter.is_none();
+        let reporter: &mut dyn Reporter = match reporter {
+            _ if self.args.isolated_child => {
+                child_reporter = ChildReporter;
+                &mut child_reporter
+            }
+            Some(reporter) => reporter,
+            None => {
+                default_reporter = reporter::from_format(self.printer.clone());
+                &mut *default_reporter
+            }
+        };
+        let progress = Progress::new(self.args, &self.printer, reporter, allow_live);
+        let isolation = if self.args.isolate {
+            match Isolation::new(self.args) {
+                Ok(isolation) => Some(isolation),
+                Err(err) => {
+                    progress.error(format_args!(
+                        "failed to locate the test executable: {}",
+                        err
+                    ));
+                    return Err(ExitStatus::FAILED);
+                }
+            }
+        } else {
+            None
+        };
+        // In the isolated mode, the test cases are run by the child processes
+        // instead of the runner.
+        let mut runner = |desc: TestDesc, data: D| match isolation {
+            Some(ref isolation) => Either::Right(isolation.run(&desc)),
+            None => Either::Left(runner.run(desc, data)),
+        };
+        let artifacts = Artifacts::new(self.args);
 
-        // First, convert each test case to PendingTest for tracking the running state.
-        // Test cases that satisfy the skip condition are filtered out here.
-        let mut pending_tests = vec![];
+        // First, select the test cases to be run.
+        // Test cases that satisfy the skip condition, or belong to other shards
+        // are filtered out here.
+        let mut selected_tests = vec![];
         let mut filtered_out_tests = vec![];
-        let mut unique_test_names = HashSet::new();
+        let mut tests: Vec<Test<D>> = tests.into_iter().collect();
+        self.resolve_duplicates(&mut tests, &progress)?;
         for test in tests {
-            if !unique_test_names.insert(test.desc().name().to_string()) {
-                let _ = writeln!(
-                    self.printer.term(),
-                    "the test name is conflicted: {}",
-                    test.desc().name()
-                );
-                return Err(ExitStatus::FAILED);
-            }
-
-            if self.args.is_filtered(test.desc().name()) {
+            if self.args.is_filtered(test.desc()) {
                 filtered_out_tests.push(test);
                 continue;
             }
 
+            selected_tests.push(test);
+        }
+
+        if let Some(shard) = self.shard(&progress)? {
+            let names: Vec<&str> = selected_tests
+                .iter()
+                .map(|test| test.desc().name())
+                .collect();
+            let in_shard = shard.select(&names);
+            let (tests, others): (Vec<_>, Vec<_>) = selected_tests
+                .into_iter()
+                .zip(in_shard)
+                .partition(|&(_, in_shard)| in_shard);
+            selected_tests = tests.into_iter().map(|(test, _)| test).collect();
+            filtered_out_tests.extend(others.into_iter().map(|(test, _)| test));
+        }
+
+        // Then, convert each test case to PendingTest for tracking the running state.
+        let snapshot_options = SnapshotOptions::new(self.args.bless);
+        let mut pending_tests = vec![];
+        for test in selected_tests {
             // Since PendingTest may contain the immovable state must be pinned
             // before starting any operations.
             // Here, each test case is allocated on the heap.
-            let (desc, context) = test.deconstruct();
+            let (mut desc, context) = test.deconstruct();
+            desc.set_bench_options(self.args.bench_options());
+            desc.set_snapshot_options(snapshot_options.clone());
             pending_tests.push(Box::pin(PendingTest {
                 desc,
                 context: Some(context),
                 test_case: None,
+                saving: None,
                 outcome: None,
-                printer: &self.printer,
-                name_length: 0,
+                elapsed: None,
+                started: false,
+                cancelled: false,
+                progress: &progress,
+                artifacts: &artifacts,
             }));
         }
 
@@ -156,44 +322,160 @@ impl<'a> TestDriver<'a> {
             return Err(ExitStatus::OK);
         }
 
-        let _ = writeln!(self.printer.term(), "running {} tests", pending_tests.len());
+        let descs: Vec<TestDesc> = pending_tests.iter().map(|test| test.desc.clone()).collect();
+        progress.suite_started(&descs);
+
+        // Spawning all the processes at once would overload the machine.
+        let concurrency = isolation
+            .as_ref()
+            .map(|_| std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get));
+
+        // Ctrl-C is observed until the summary is reported.
+        let _interrupt = interrupt::arm();
+        let stopped = {
+            let run_tests = futures_util::stream::iter(pending_tests.iter_mut()) //
+                .for_each_concurrent(concurrency, |test| {
+                    test.as_mut().start(self.args, &mut runner);
+                    test
+                });
+            pin_mut!(run_tests);
+
+            // Stop running the tests when the number of failures reaches the limit,
+            // or Ctrl-C is pressed.
+            // Since the failures are only observed while polling the tests,
+            // it suffices to check the condition after each poll.
+            let run_tests = future::poll_fn(|cx| {
+                if run_tests.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(None);
+                }
+                if interrupt::is_interrupted(cx.waker()) {
+                    return Poll::Ready(Some(StopReason::Interrupted));
+                }
+                match self.args.max_fail {
+                    Some(max_fail) if progress.num_failed() >= max_fail => {
+                        Poll::Ready(Some(StopReason::MaxFail(max_fail)))
+                    }
+                    _ => Poll::Pending,
+                }
+            });
 
-        let max_name_length = pending_tests
-            .iter()
-            .map(|test| test.desc.name().len())
-            .max()
-            .unwrap_or(0);
+            // Check the running tests periodically, so that the slow tests are
+            // noticed and the elapsed time in the status area is kept up to date.
+            let tick = async {
+                let interval = match progress.tick_interval() {
+                    Some(interval) => interval,
+                    None => return future::pending().await,
+                };
+                loop {
+                    Delay::new(interval).await;
+                    progress.tick();
+                }
+            };
 
-        futures_util::stream::iter(pending_tests.iter_mut()) //
-            .for_each_concurrent(None, |test| {
-                test.as_mut()
-                    .start(&self.args, max_name_length, &mut runner);
-                test
-            })
-            .await;
+            pin_mut!(run_tests, tick);
+            match future::select(run_tests, tick).await {
+                Either::Left((stopped, _)) => stopped,
+                Either::Right(((), _)) => unreachable!("the ticker never completes"),
+            }
+        };
+        if stopped.is_some() {
+            for test in &mut pending_tests {
+                test.as_mut().cancel();
+            }
+        }
+        progress.finish();
 
         let mut passed = vec![];
         let mut failed = vec![];
         let mut measured = vec![];
         let mut ignored = vec![];
+        let mut cancelled = vec![];
+        let mut not_run = vec![];
+        let mut metrics = vec![];
         for test in &pending_tests {
+            if let Some(ref outcome) = test.outcome {
+                if !outcome.metrics().is_empty() {
+                    metrics.push((test.desc.clone(), outcome.metrics().to_vec()));
+                }
+            }
             match test.outcome {
                 Some(ref outcome) => match outcome.kind() {
                     OutcomeKind::Passed => passed.push(test.desc.clone()),
-                    OutcomeKind::Failed => failed.push((test.desc.clone(), outcome.err_msg())),
-                    OutcomeKind::Measured { average, variance } => {
-                        measured.push((test.desc.clone(), (*average, *variance)))
+                    OutcomeKind::Failed => failed.push((
+                        test.desc.clone(),
+                        Failure {
+                            message: outcome.err_msg(),
+                            mismatch: outcome.shared_mismatch(),
+                            artifacts: outcome.artifacts().to_vec(),
+                        },
+                    )),
+                    OutcomeKind::Measured(stats) => {
+                        measured.push((test.desc.clone(), stats.clone()))
                     }
                 },
+                None if test.cancelled => cancelled.push(test.desc.clone()),
+                None if !test.started => not_run.push(test.desc.clone()),
                 None => ignored.push(test.desc.clone()),
             }
         }
 
+        if let Some(ref path) = self.args.save_timings {
+            let timings = pending_tests
+                .iter()
+                .filter_map(|test| Some((test.desc.name(), test.elapsed?)));
+            if let Err(err) = shard::save_timings(path, timings) {
+                progress.warning(format_args!(
+                    "failed to save the timings to {}: {}",
+                    path.display(),
+                    err
+                ));
+            }
+        }
+
+        let mut baseline_changes = vec![];
+        if let Some(ref name) = self.args.baseline {
+            match Baseline::load(name) {
+                Ok(baseline) => {
+                    baseline_changes = measured
+                        .iter()
+                        .filter_map(|(desc, stats)| {
+                            let change =
+                                baseline.compare(desc.name(), stats, self.args.bench_threshold)?;
+                            Some((desc.clone(), change))
+                        })
+                        .collect();
+                }
+                Err(err) => progress.warning(format_args!(
+                    "failed to load the baseline `{}`: {}",
+                    name, err
+                )),
+            }
+        }
+        match self.args.save_baseline {
+            // Do not create an empty baseline when no benchmark was run, e.g. without `--bench`.
+            Some(_) if measured.is_empty() => {}
+            Some(ref name) => {
+                if let Err(err) = Baseline::save(name, &measured) {
+                    progress.warning(format_args!(
+                        "failed to save the baseline `{}`: {}",
+                        name, err
+                    ));
+                }
+            }
+            None => {}
+        }
+
         let report = Report {
             passed,
             failed,
             measured,
+            baseline_changes,
+            metrics,
             ignored,
+            cancelled,
+            not_run,
+            stopped,
+            snapshots_updated: snapshot_options.updated(),
             filtered_out: filtered_out_tests
                 .into_iter()
                 .map(|test| {
@@ -202,7 +484,7 @@ impl<'a> TestDriver<'a> {
                 })
                 .collect(),
         };
-        let _ = report.print(&self.printer);
+        progress.summary(&report);
 
         Ok(report)
     }
@@ -0,0 +1,80 @@
+//! Detection of the tests sharing the same name.
+
+use std::collections::{HashMap, HashSet};
+
+/// Return the names appearing more than once, with their 1-based positions.
+///
+/// The names are ordered by their first appearance.
+pub(crate) fn find_duplicates<'a>(names: &[&'a str]) -> Vec<(&'a str, Vec<usize>)> {
+    let mut positions: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut order = vec![];
+    for (i, &name) in names.iter().enumerate() {
+        let entry = positions.entry(name).or_default();
+        if entry.is_empty() {
+            order.push(name);
+        }
+        entry.push(i + 1);
+    }
+    order
+        .into_iter()
+        .filter_map(|name| {
+            let positions = positions.remove(name)?;
+            if positions.len() > 1 {
+                Some((name, positions))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Return the new names of the duplicated tests, with the numeric suffix like `name#2`.
+///
+/// The first test keeps its name, and the suffixes are chosen so as not to
+/// conflict with the other names.
+pub(crate) fn disambiguate(names: &[&str]) -> Vec<Option<String>> {
+    let mut taken: HashSet<String> = names.iter().map(|&name| name.to_owned()).collect();
+    let mut seen = HashSet::new();
+    names
+        .iter()
+        .map(|&name| {
+            if seen.insert(name) {
+                return None;
+            }
+            let renamed = (2..)
+                .map(|n| format!("{}#{}", name, n))
+                .find(|renamed| !taken.contains(renamed))
+                .expect("the suffixes should not be exhausted");
+            taken.insert(renamed.clone());
+            Some(renamed)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicates_with_positions() {
+        assert_eq!(
+            find_duplicates(&["a", "b", "c", "b", "a", "b"]),
+            vec![("a", vec![1, 5]), ("b", vec![2, 4, 6])]
+        );
+        assert!(find_duplicates(&["a", "b"]).is_empty());
+    }
+
+    #[test]
+    fn disambiguate_with_suffix() {
+        assert_eq!(
+            disambiguate(&["a", "a#2", "b", "a", "a"]),
+            vec![
+                None,
+                None,
+                None,
+                Some("a#3".to_owned()),
+                Some("a#4".to_owned())
+            ]
+        );
+    }
+}
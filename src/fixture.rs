@@ -0,0 +1,154 @@
+//! Discovery of the fixture files used by `Test::from_dir`.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Resolve the path relative to the manifest directory of the package.
+///
+/// The current directory is used as the base when the test binary is not run by Cargo.
+pub(crate) fn resolve_path(path: &Path) -> PathBuf {
+    match env::var_os("CARGO_MANIFEST_DIR") {
+        Some(base) if path.is_relative() => Path::new(&base).join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Return the files under `dir` matching the glob pattern, with their paths
+/// relative to `dir` separated by `/`.
+///
+/// The files are sorted by their relative paths. The symbolic links to
+/// directories are not followed, so that the links to the ancestors do not
+/// make the walk loop forever.
+pub(crate) fn find_files(dir: &Path, pattern: &str) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut files = vec![];
+    walk(dir, "", pattern, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn walk(
+    dir: &Path,
+    prefix: &str,
+    pattern: &str,
+    files: &mut Vec<(String, PathBuf)>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = match name.to_str() {
+            Some(name) => name,
+            // Such files cannot be matched with the pattern anyway.
+            None => continue,
+        };
+        let relative = format!("{}{}", prefix, name);
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(&path, &format!("{}/", relative), pattern, files)?;
+        } else if file_type.is_symlink() && path.is_dir() {
+            continue;
+        } else if glob_match(pattern, &relative) {
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+/// Match the `/`-separated path with the glob pattern.
+///
+/// `*` matches any characters except `/`, `?` matches a single character
+/// except `/`, and `**` as a path segment matches zero or more segments.
+pub(crate) fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    match_segments(&pattern, &path)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| match_segments(rest, &path[i..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path)) => match_segment(segment, name) && match_segments(rest, path),
+            None => false,
+        },
+    }
+}
+
+fn match_segment(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // Backtrack to the last `*` on mismatch.
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_segment() {
+        assert!(glob_match("*.json", "a.json"));
+        assert!(glob_match("*.json", ".json"));
+        assert!(!glob_match("*.json", "a.jsonc"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(!glob_match("a?c", "ac"));
+        assert!(glob_match("*a*b", "xaxxb"));
+        assert!(!glob_match("*a*b", "xaxxbx"));
+        assert!(glob_match("*", "anything"));
+    }
+
+    #[test]
+    fn glob_path() {
+        assert!(!glob_match("*.json", "dir/a.json"));
+        assert!(glob_match("*/*.json", "dir/a.json"));
+        assert!(glob_match("**/*.json", "a.json"));
+        assert!(glob_match("**/*.json", "dir/sub/a.json"));
+        assert!(glob_match("dir/**", "dir/sub/a.json"));
+        assert!(glob_match("dir/**/a.json", "dir/a.json"));
+        assert!(!glob_match("dir/**/a.json", "other/a.json"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_to_directories() -> io::Result<()> {
+        let dir = env::temp_dir().join(format!("mimicaw-fixture-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub"))?;
+        fs::write(dir.join("a.json"), "")?;
+        fs::write(dir.join("sub/b.json"), "")?;
+        std::os::unix::fs::symlink(&dir, dir.join("sub/loop"))?;
+        std::os::unix::fs::symlink(dir.join("a.json"), dir.join("sub/c.json"))?;
+
+        let files = find_files(&dir, "**/*.json");
+        fs::remove_dir_all(&dir)?;
+        let names: Vec<_> = files?.into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["a.json", "sub/b.json", "sub/c.json"]);
+        Ok(())
+    }
+}
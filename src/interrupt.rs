@@ -0,0 +1,66 @@
+//! Handling of the interruption by Ctrl-C.
+
+use futures_core::task::Waker;
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Mutex, Once,
+};
+
+static INSTALL: Once = Once::new();
+static ACTIVE_RUNS: AtomicUsize = AtomicUsize::new(0);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static WAKER: Mutex<Option<Waker>> = Mutex::new(None);
+
+/// The exit code used when the process is terminated by Ctrl-C.
+const EXIT_CODE_INTERRUPTED: i32 = 130;
+
+/// The guard that keeps observing Ctrl-C during a test run.
+pub(crate) struct Armed(());
+
+impl Drop for Armed {
+    fn drop(&mut self) {
+        ACTIVE_RUNS.fetch_sub(1, Ordering::SeqCst);
+        WAKER.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+}
+
+/// Start observing Ctrl-C until the returned guard is dropped.
+///
+/// While a test run is active, the first Ctrl-C requests to stop the test run,
+/// and the second one terminates the process immediately. Otherwise, Ctrl-C
+/// terminates the process as if the handler were not installed.
+/// If another handler has already been installed by the user, the
+/// interruption is not observed by the driver.
+pub(crate) fn arm() -> Armed {
+    // The handler cannot be uninstalled, so it is installed only once and
+    // checks whether a test run is active.
+    INSTALL.call_once(|| {
+        let _ = ctrlc::set_handler(|| {
+            if ACTIVE_RUNS.load(Ordering::SeqCst) == 0 || INTERRUPTED.swap(true, Ordering::SeqCst) {
+                std::process::exit(EXIT_CODE_INTERRUPTED);
+            }
+            if let Some(waker) = WAKER.lock().unwrap_or_else(|e| e.into_inner()).take() {
+                waker.wake();
+            }
+        });
+    });
+    if ACTIVE_RUNS.fetch_add(1, Ordering::SeqCst) == 0 {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+    }
+    Armed(())
+}
+
+/// Return whether Ctrl-C has been pressed.
+///
+/// If not, the specified waker is woken up when it is pressed.
+pub(crate) fn is_interrupted(waker: &Waker) -> bool {
+    let mut slot = WAKER.lock().unwrap_or_else(|e| e.into_inner());
+    if INTERRUPTED.load(Ordering::SeqCst) {
+        return true;
+    }
+    match *slot {
+        Some(ref current) if current.will_wake(waker) => (),
+        _ => *slot = Some(waker.clone()),
+    }
+    false
+}
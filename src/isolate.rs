@@ -0,0 +1,331 @@
+//! Running each test case in a separate process, enabled by `--isolate`.
+//!
+//! The driver re-invokes the current executable with the name of the test
+//! case and `--exact`. The child process reports the outcome as a JSON line
+//! with the prefix `RESULT_PREFIX` on stdout, and the parent converts it back
+//! to `Outcome`. The number of snapshots updated by `--bless` is reported on
+//! the same line and added to the count of the parent. If the child process
+//! terminates without reporting the outcome, the test case is regarded as failed.
+
+use crate::{
+    args::Args,
+    bench::BenchStats,
+    json::{self, Value},
+    printer,
+    report::Report,
+    reporter::Reporter,
+    snapshot::SnapshotOptions,
+    test::{Outcome, OutcomeKind, TestDesc},
+};
+use futures_channel::oneshot;
+use futures_core::{
+    future::Future,
+    task::{self, Poll},
+};
+use futures_util::ready;
+use std::{
+    env,
+    io::{self, Read, Write},
+    path::PathBuf,
+    pin::Pin,
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// The environment variable that tells the process to run as a child.
+pub(crate) const CHILD_ENV: &str = "MIMICAW_ISOLATED_CHILD";
+
+/// The prefix of the line on which the child process reports the outcome.
+pub(crate) const RESULT_PREFIX: &str = "mimicaw-isolated-result: ";
+
+/// The number of lines of stderr included in the message of crashed tests.
+const STDERR_TAIL_LINES: usize = 20;
+
+pub(crate) struct Isolation<'a> {
+    exe: PathBuf,
+    args: &'a Args,
+}
+
+impl<'a> Isolation<'a> {
+    pub(crate) fn new(args: &'a Args) -> io::Result<Self> {
+        Ok(Self {
+            exe: env::current_exe()?,
+            args,
+        })
+    }
+
+    fn command(&self, desc: &TestDesc) -> Command {
+        let mut command = Command::new(&self.exe);
+        command
+            .env(CHILD_ENV, "1")
+            .args(self.args.child_args(desc))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    }
+
+    /// Spawn the child process that runs the test case.
+    pub(crate) fn run(&self, desc: &TestDesc) -> IsolatedTest {
+        let (tx, rx) = oneshot::channel();
+        let snapshot_options = desc.snapshot_options().clone();
+        let child = match self.command(desc).spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                let _ = tx.send(Err(err));
+                return IsolatedTest {
+                    rx,
+                    child: None,
+                    snapshot_options,
+                };
+            }
+        };
+        let child = Arc::new(Mutex::new(child));
+        let waiting = child.clone();
+        thread::spawn(move || {
+            let _ = tx.send(wait_with_output(&waiting));
+        });
+        IsolatedTest {
+            rx,
+            child: Some(child),
+            snapshot_options,
+        }
+    }
+}
+
+fn wait_with_output(child: &Mutex<Child>) -> io::Result<Output> {
+    let (stdout, stderr) = {
+        let mut child = child.lock().unwrap_or_else(|e| e.into_inner());
+        (child.stdout.take(), child.stderr.take())
+    };
+    let read_stderr = thread::spawn(move || -> io::Result<Vec<u8>> {
+        let mut buf = vec![];
+        if let Some(mut stderr) = stderr {
+            stderr.read_to_end(&mut buf)?;
+        }
+        Ok(buf)
+    });
+    let mut stdout_buf = vec![];
+    if let Some(mut stdout) = stdout {
+        stdout.read_to_end(&mut stdout_buf)?;
+    }
+    let stderr_buf = read_stderr
+        .join()
+        .unwrap_or_else(|_| Err(io::Error::other("failed to read stderr")))?;
+
+    // Both pipes are closed here, so the process is about to exit and
+    // the lock is not held for long.
+    let status = child.lock().unwrap_or_else(|e| e.into_inner()).wait()?;
+    Ok(Output {
+        status,
+        stdout: stdout_buf,
+        stderr: stderr_buf,
+    })
+}
+
+/// The future that waits for the child process running a test case.
+///
+/// The child process is killed if the future is dropped before completion.
+pub(crate) struct IsolatedTest {
+    rx: oneshot::Receiver<io::Result<Output>>,
+    child: Option<Arc<Mutex<Child>>>,
+    snapshot_options: SnapshotOptions,
+}
+
+impl Future for IsolatedTest {
+    type Output = Outcome;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let output = ready!(Pin::new(&mut self.rx).poll(cx));
+        self.child.take();
+        Poll::Ready(match output {
+            Ok(Ok(output)) => outcome_from_output(&output, &self.snapshot_options),
+            Ok(Err(err)) => {
+                Outcome::failed().error_message(format!("failed to run the test process: {}", err))
+            }
+            Err(oneshot::Canceled) => {
+                Outcome::failed().error_message("the test process was lost unexpectedly")
+            }
+        })
+    }
+}
+
+impl Drop for IsolatedTest {
+    fn drop(&mut self) {
+        if let Some(child) = self.child.take() {
+            let _ = child.lock().unwrap_or_else(|e| e.into_inner()).kill();
+        }
+    }
+}
+
+fn outcome_from_output(output: &Output, snapshot_options: &SnapshotOptions) -> Outcome {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let event = stdout
+        .lines()
+        .rev()
+        .filter_map(|line| line.strip_prefix(RESULT_PREFIX))
+        .find_map(json::parse);
+    if let Some(updated) = event
+        .as_ref()
+        .and_then(|event| event.get("snapshots_updated"))
+        .and_then(Value::as_f64)
+    {
+        snapshot_options.add_updated(updated as usize);
+    }
+    let reported = event.and_then(|event| outcome_from_event(&event));
+
+    match reported {
+        // The failed child exits with the non-zero status as usual.
+        Some(outcome)
+            if output.status.success() || matches!(outcome.kind(), OutcomeKind::Failed) =>
+        {
+            outcome
+        }
+        Some(..) => crashed(output, "after the test completed"),
+        None => crashed(output, "without reporting the result"),
+    }
+}
+
+fn outcome_from_event(event: &Value) -> Option<Outcome> {
+    let mut outcome = match event.get("type")?.as_str()? {
+        "test" => match event.get("event")?.as_str()? {
+            "ok" => Outcome::passed(),
+            "failed" => {
+                let expected = event.get("expected").and_then(Value::as_str);
+                let actual = event.get("actual").and_then(Value::as_str);
+                let outcome = match (expected, actual) {
+                    (Some(expected), Some(actual)) => Outcome::mismatch(expected, actual),
+                    _ => Outcome::failed(),
+                };
+                let mut outcome = match event.get("message").and_then(Value::as_str) {
+                    Some(msg) => outcome.error_message(msg.to_owned()),
+                    None => outcome,
+                };
+                for path in event
+                    .get("artifacts")
+                    .and_then(Value::as_array)
+                    .unwrap_or(&[])
+                {
+                    outcome.add_artifact(path.as_str()?.into());
+                }
+                outcome
+            }
+            _ => return None,
+        },
+        "bench" => Outcome::measured_with_stats(bench_stats_from_event(event)?),
+        _ => return None,
+    };
+    for metric in event
+        .get("metrics")
+        .and_then(Value::as_array)
+        .unwrap_or(&[])
+    {
+        outcome = outcome.metric(
+            metric.get("name")?.as_str()?.to_owned(),
+            // Non-finite values are written as null.
+            metric.get("value")?.as_f64().unwrap_or(f64::NAN),
+            metric.get("unit")?.as_str()?.to_owned(),
+        );
+    }
+    Some(outcome)
+}
+
+fn bench_stats_from_event(event: &Value) -> Option<BenchStats> {
+    let field = |key: &str| event.get(key).and_then(Value::as_f64);
+    let samples = field("samples")? as usize;
+    let average = field("average")? as u64;
+    let variance = field("deviation")? as u64;
+    if samples == 0 {
+        return Some(BenchStats::from_average(average, variance));
+    }
+    Some(BenchStats {
+        samples,
+        average,
+        variance,
+        min: field("min")? as u64,
+        max: field("max")? as u64,
+        median: field("median")? as u64,
+        p25: field("p25")? as u64,
+        p75: field("p75")? as u64,
+        p90: field("p90")? as u64,
+        p99: field("p99")? as u64,
+        outliers_low: field("outliers_low")? as usize,
+        outliers_high: field("outliers_high")? as usize,
+    })
+}
+
+fn crashed(output: &Output, when: &str) -> Outcome {
+    let mut msg = format!(
+        "the test process {} {}\n",
+        describe_status(output.status),
+        when
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    if !lines.is_empty() {
+        let tail = &lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..];
+        msg += &format!("stderr (last {} lines):\n", tail.len());
+        for line in tail {
+            msg += line;
+            msg.push('\n');
+        }
+    }
+    Outcome::failed().error_message(msg)
+}
+
+fn describe_status(status: ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return match signal_name(signal) {
+                Some(name) => format!("was terminated by signal {} ({})", signal, name),
+                None => format!("was terminated by signal {}", signal),
+            };
+        }
+    }
+    match status.code() {
+        Some(code) => format!("exited with code {}", code),
+        None => "exited abnormally".to_owned(),
+    }
+}
+
+#[cfg(unix)]
+fn signal_name(signal: i32) -> Option<&'static str> {
+    Some(match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        4 => "SIGILL",
+        6 => "SIGABRT",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        13 => "SIGPIPE",
+        15 => "SIGTERM",
+        _ => return None,
+    })
+}
+
+/// The reporter used in the child process, which writes only the outcome for the parent.
+pub(crate) struct ChildReporter;
+
+impl Reporter for ChildReporter {
+    fn suite_start(&mut self, _: &[TestDesc]) {}
+
+    fn test_result(&mut self, desc: &TestDesc, outcome: Option<&Outcome>) {
+        // The child runs only this test case, so all the updated snapshots belong to it.
+        let mut event = printer::result_to_json(desc, outcome);
+        let updated = desc.snapshot_options().updated();
+        if updated > 0 {
+            event = event.number("snapshots_updated", updated);
+        }
+        let event = event.finish();
+        // The output of the test case may not end with a newline.
+        let mut stdout = io::stdout();
+        let _ = writeln!(stdout, "\n{}{}", RESULT_PREFIX, event);
+        let _ = stdout.flush();
+    }
+
+    fn summary(&mut self, _: &Report) {}
+}
@@ -0,0 +1,329 @@
+//! A minimal writer and reader of JSON used by the machine-readable output.
+
+use std::fmt::{self, Write};
+
+/// A builder of a flat JSON object.
+pub(crate) struct Object {
+    buf: String,
+}
+
+impl Object {
+    pub(crate) fn new() -> Self {
+        Self {
+            buf: String::from("{"),
+        }
+    }
+
+    fn key(&mut self, key: &str) {
+        if self.buf.len() > 1 {
+            self.buf.push_str(", ");
+        }
+        write_str(&mut self.buf, key);
+        self.buf.push_str(": ");
+    }
+
+    /// Append a field with the string value.
+    pub(crate) fn string(mut self, key: &str, value: &str) -> Self {
+        self.key(key);
+        write_str(&mut self.buf, value);
+        self
+    }
+
+    /// Append a field with the numeric value.
+    pub(crate) fn number(mut self, key: &str, value: impl fmt::Display) -> Self {
+        self.key(key);
+        let _ = write!(self.buf, "{}", value);
+        self
+    }
+
+    /// Append a field with the floating-point value.
+    ///
+    /// Non-finite values are written as `null`.
+    pub(crate) fn float(mut self, key: &str, value: f64) -> Self {
+        self.key(key);
+        if value.is_finite() {
+            let _ = write!(self.buf, "{}", value);
+        } else {
+            self.buf.push_str("null");
+        }
+        self
+    }
+
+    /// Append a field with the array of objects.
+    pub(crate) fn objects(mut self, key: &str, values: impl IntoIterator<Item = Object>) -> Self {
+        self.key(key);
+        self.buf.push('[');
+        for (i, value) in values.into_iter().enumerate() {
+            if i > 0 {
+                self.buf.push_str(", ");
+            }
+            self.buf.push_str(&value.finish());
+        }
+        self.buf.push(']');
+        self
+    }
+
+    /// Append a field with the array of strings.
+    pub(crate) fn strings<'a>(
+        mut self,
+        key: &str,
+        values: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        self.key(key);
+        self.buf.push('[');
+        for (i, value) in values.into_iter().enumerate() {
+            if i > 0 {
+                self.buf.push_str(", ");
+            }
+            write_str(&mut self.buf, value);
+        }
+        self.buf.push(']');
+        self
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        self.buf.push('}');
+        self.buf
+    }
+}
+
+/// Write the string as a JSON string literal.
+pub(crate) fn write_str(buf: &mut String, s: &str) {
+    buf.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            ch if ch < ' ' => {
+                let _ = write!(buf, "\\u{:04x}", ch as u32);
+            }
+            ch => buf.push(ch),
+        }
+    }
+    buf.push('"');
+}
+
+/// A parsed JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Return the field of the object with the specified key.
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a JSON document, such as the line written by `Object`.
+pub(crate) fn parse(s: &str) -> Option<Value> {
+    let mut parser = Parser {
+        chars: s.chars().peekable(),
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    match parser.chars.next() {
+        None => Some(value),
+        Some(..) => None,
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> Option<()> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Some(()),
+            _ => None,
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Value) -> Option<Value> {
+        for expected in keyword.chars() {
+            if self.chars.next()? != expected {
+                return None;
+            }
+        }
+        Some(value)
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        match *self.chars.peek()? {
+            '{' => self.object(),
+            '[' => self.array(),
+            '"' => self.string().map(Value::String),
+            'n' => self.keyword("null", Value::Null),
+            't' => self.keyword("true", Value::Bool(true)),
+            'f' => self.keyword("false", Value::Bool(false)),
+            _ => self.number(),
+        }
+    }
+
+    fn object(&mut self) -> Option<Value> {
+        self.eat('{')?;
+        let mut fields = vec![];
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&'}') {
+            self.chars.next();
+            return Some(Value::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.eat(':')?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.chars.next()? {
+                ',' => continue,
+                '}' => return Some(Value::Object(fields)),
+                _ => return None,
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<Value> {
+        self.eat('[')?;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&']') {
+            self.chars.next();
+            return Some(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.next()? {
+                ',' => continue,
+                ']' => return Some(Value::Array(values)),
+                _ => return None,
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.chars.next()? != '"' {
+            return None;
+        }
+        let mut s = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(s),
+                '\\' => match self.chars.next()? {
+                    '"' => s.push('"'),
+                    '\\' => s.push('\\'),
+                    '/' => s.push('/'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'u' => {
+                        let code: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                        let code = u32::from_str_radix(&code, 16).ok()?;
+                        s.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                    }
+                    _ => return None,
+                },
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Option<Value> {
+        let mut s = String::new();
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                '0'..='9' | '-' | '+' | '.' | 'e' | 'E' => {
+                    s.push(c);
+                    self.chars.next();
+                }
+                _ => break,
+            }
+        }
+        s.parse().ok().map(Value::Number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_written_object() {
+        let line = Object::new()
+            .string("name", "foo \"bar\"\n\tbaz \u{1}")
+            .number("count", 42)
+            .float("ratio", -1.5e-3)
+            .objects(
+                "items",
+                vec![Object::new().string("unit", "B/s"), Object::new()],
+            )
+            .finish();
+        let value = parse(&line).unwrap();
+        assert_eq!(
+            value.get("name").and_then(Value::as_str),
+            Some("foo \"bar\"\n\tbaz \u{1}")
+        );
+        assert_eq!(value.get("count").and_then(Value::as_f64), Some(42.0));
+        assert_eq!(value.get("ratio").and_then(Value::as_f64), Some(-1.5e-3));
+        let items = value.get("items").and_then(Value::as_array).unwrap();
+        assert_eq!(items[0].get("unit").and_then(Value::as_str), Some("B/s"));
+        assert_eq!(items[1], Value::Object(vec![]));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(parse("{\"a\": 1"), None);
+        assert_eq!(parse("{\"a\": 1} trailing"), None);
+        assert_eq!(
+            parse("[true, false, null]")
+                .unwrap()
+                .as_array()
+                .map(|a| a.len()),
+            Some(3)
+        );
+    }
+}
@@ -56,19 +56,65 @@ status.exit()
 #![forbid(unsafe_code, clippy::unimplemented, clippy::todo)]
 
 mod args;
+mod artifacts;
+mod baseline;
+mod bench;
+mod blocking;
+mod case;
+mod diff;
 mod driver;
+mod duplicates;
+mod fixture;
+mod interrupt;
+mod isolate;
+mod json;
+mod params;
 mod printer;
+mod progress;
+#[cfg(feature = "macros")]
+mod registry;
 mod report;
+mod reporter;
+mod shard;
+mod snapshot;
+mod spawn;
+mod storage;
 mod test;
 
+#[cfg(feature = "async-std")]
+pub mod async_std;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
 pub use crate::{
-    args::{Args, ColorConfig, OutputFormat},
+    args::{Args, ColorConfig, DuplicatePolicy, OutputFormat},
+    baseline::BenchChange,
+    bench::{BenchStats, Bencher},
+    blocking::{BlockingRunner, BlockingTest},
+    case::{DefaultRunner, TestCase},
     driver::TestRunner,
-    report::Report,
-    test::{Outcome, Test, TestDesc},
+    params::{Matrix, Params},
+    report::{Failure, Report, StopReason},
+    reporter::{JsonReporter, PrettyReporter, Reporter, TerseReporter},
+    spawn::{SpawnRunner, Spawner},
+    test::{Attachment, Metric, Mismatch, Outcome, OutcomeKind, Test, TestDesc},
 };
 
+#[cfg(feature = "macros")]
+pub use crate::registry::collected_tests;
+#[cfg(feature = "macros")]
+pub use mimicaw_macros::test;
+
+// Not public API, used by the code generated by `#[mimicaw::test]`.
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::registry::{test_name, Registration};
+    pub use inventory;
+}
+
 use crate::driver::TestDriver;
+use std::io::{self, Write};
 
 /// Exit status code used as a result of the test process.
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -77,6 +123,7 @@ pub struct ExitStatus(i32);
 impl ExitStatus {
     const OK: Self = Self(0);
     const FAILED: Self = Self(101);
+    const INTERRUPTED: Self = Self(130);
 
     /// Return whether the status is successful or not.
     #[inline]
@@ -84,6 +131,12 @@ impl ExitStatus {
         self.code() == 0
     }
 
+    /// Return whether the test run was interrupted by Ctrl-C.
+    #[inline]
+    pub fn interrupted(self) -> bool {
+        self == Self::INTERRUPTED
+    }
+
     /// Return the raw exit code.
     #[inline]
     pub fn code(self) -> i32 {
@@ -92,10 +145,13 @@ impl ExitStatus {
 
     /// Terminate the test process with the exit code.
     ///
+    /// The standard output and error are flushed before terminating.
     /// This method **should not** be called before the cleanup
     /// of the test process has completed.
     #[inline]
     pub fn exit(self) -> ! {
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
         std::process::exit(self.code());
     }
 
@@ -122,6 +178,13 @@ impl ExitStatus {
 ///   these futures are executed concurrently, and their results
 ///   are written to the console in the order of completion.
 /// * Finally, the results of all test cases are aggregated.
+///
+/// Ctrl-C stops the test run and reports the partial results. For this, the first
+/// test run installs a process-wide handler of Ctrl-C with the `ctrlc` crate. The handler
+/// cannot be removed, so Ctrl-C outside of the test runs terminates the process with
+/// the exit code 130 as usual, but the handlers installed by the user afterwards with
+/// `ctrlc` fail. Conversely, if a handler has already been installed before the first
+/// test run, the driver does not observe Ctrl-C.
 pub async fn run_tests<D>(
     args: &Args,
     tests: impl IntoIterator<Item = Test<D>>,
@@ -139,11 +202,41 @@ pub async fn run_tests_with_report<D>(
     tests: impl IntoIterator<Item = Test<D>>,
     runner: impl TestRunner<D>,
 ) -> Result<Report, ExitStatus> {
-    let driver = TestDriver::new(&args);
-    driver.run_tests(tests, runner).await
+    let driver = TestDriver::new(args);
+    driver.run_tests(tests, runner, None).await
+}
+
+/// Run a test suite and send the results to the specified reporter, instead of
+/// writing them to the console.
+///
+/// The live status area enabled by `--progress` is not displayed. The warnings
+/// about slow tests are still written to the console.
+pub async fn run_tests_with_reporter<D>(
+    args: &Args,
+    tests: impl IntoIterator<Item = Test<D>>,
+    runner: impl TestRunner<D>,
+    reporter: &mut dyn Reporter,
+) -> Result<Report, ExitStatus> {
+    let driver = TestDriver::new(args);
+    driver.run_tests(tests, runner, Some(reporter)).await
 }
 
-#[test]
-fn test_html_root_url() {
-    version_sync::assert_html_root_url_updated!("src/lib.rs");
+/// Run a test suite on the built-in executor and terminate the process.
+///
+/// This is a shortcut for parsing the command line arguments with
+/// `Args::from_env`, blocking on `run_tests` and exiting with the status.
+/// The test cases are polled on the current thread.
+pub fn main<D>(tests: impl IntoIterator<Item = Test<D>>, runner: impl TestRunner<D>) -> ! {
+    let args = Args::from_env().unwrap_or_else(|st| st.exit());
+    futures_executor::block_on(run_tests(&args, tests, runner)).exit()
+}
+
+// Placed in a module, since `#[test]` at the crate root may refer to
+// the re-exported attribute macro.
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_html_root_url() {
+        version_sync::assert_html_root_url_updated!("src/lib.rs");
+    }
 }
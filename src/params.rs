@@ -0,0 +1,230 @@
+use crate::test::Test;
+use std::{borrow::Cow, fmt::Display};
+
+/// The parameters of a test generated by `Test::cases` or `Matrix`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Params(pub(crate) Vec<(Cow<'static, str>, String)>);
+
+impl Params {
+    /// Return the value of the parameter formatted as a string.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.iter()
+            .find(|&(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Return an iterator of the pairs of parameter name and value.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.0.iter().map(|(key, value)| (&**key, &**value))
+    }
+
+    /// Return the number of parameters.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Return whether the test has no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Return the name of the test, in the form of `base[key=value,...]`.
+    ///
+    /// The characters delimiting the parameters in the keys and values,
+    /// that is `,`, `=`, `]` and `\`, are escaped with `\`.
+    pub(crate) fn test_name(&self, base: &str) -> String {
+        let params: Vec<String> = self
+            .iter()
+            .map(|(key, value)| format!("{}={}", escape(key), escape(value)))
+            .collect();
+        format!("{}[{}]", base, params.join(","))
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    matches!(c, ',' | '=' | ']' | '\\')
+}
+
+fn escape(s: &str) -> Cow<'_, str> {
+    if !s.contains(is_delimiter) {
+        return Cow::Borrowed(s);
+    }
+    let mut escaped = String::with_capacity(s.len() + 1);
+    for c in s.chars() {
+        if is_delimiter(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    Cow::Owned(escaped)
+}
+
+/// A builder of the tests for all the combinations of parameters.
+///
+/// ```
+/// # use mimicaw::Matrix;
+/// let tests = Matrix::new("add")
+///     .param("x", vec![1, 2])
+///     .param("y", vec![10, 20])
+///     .tests(|params| {
+///         let x: i32 = params.get("x").unwrap().parse().unwrap();
+///         let y: i32 = params.get("y").unwrap().parse().unwrap();
+///         (x, y)
+///     });
+/// assert_eq!(tests.len(), 4); // add[x=1,y=10], add[x=1,y=20], ...
+/// ```
+#[derive(Debug, Clone)]
+pub struct Matrix {
+    base: Cow<'static, str>,
+    params: Vec<(Cow<'static, str>, Vec<String>)>,
+}
+
+impl Matrix {
+    /// Create a matrix of the tests with the specified base name.
+    pub fn new(base: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            base: base.into(),
+            params: vec![],
+        }
+    }
+
+    /// Add a parameter taking each of the values.
+    pub fn param<T: Display>(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        values: impl IntoIterator<Item = T>,
+    ) -> Self {
+        let values = values.into_iter().map(|value| value.to_string()).collect();
+        self.params.push((name.into(), values));
+        self
+    }
+
+    /// Create a test for each combination of the parameters.
+    ///
+    /// The combinations are ordered so that the last parameter varies fastest.
+    pub fn tests<D>(&self, mut f: impl FnMut(&Params) -> D) -> Vec<Test<D>> {
+        let mut combinations = vec![Params::default()];
+        for (name, values) in &self.params {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|params| {
+                    values.iter().map(move |value| {
+                        let mut params = params.clone();
+                        params.0.push((name.clone(), value.clone()));
+                        params
+                    })
+                })
+                .collect();
+        }
+        combinations
+            .into_iter()
+            .map(|params| {
+                let data = f(&params);
+                Test::with_params(self.base.clone(), params, data)
+            })
+            .collect()
+    }
+}
+
+/// Parse the filter of the form `base[key=value,...]`, unescaping the keys and values.
+pub(crate) fn parse_filter(filter: &str) -> Option<(&str, Vec<(String, String)>)> {
+    let (base, rest) = filter.split_at(filter.find('[')?);
+
+    let mut params = vec![];
+    let mut key = None;
+    let mut token = String::new();
+    let mut chars = rest[1..].chars();
+    loop {
+        match chars.next()? {
+            '\\' => token.push(chars.next()?),
+            '=' if key.is_none() => key = Some(std::mem::take(&mut token)),
+            c @ ',' | c @ ']' => {
+                match key.take() {
+                    Some(key) => params.push((key.trim().to_owned(), token.trim().to_owned())),
+                    None if token.trim().is_empty() => (),
+                    None => return None,
+                }
+                token.clear();
+                if c == ']' {
+                    break;
+                }
+            }
+            '=' => return None,
+            c => token.push(c),
+        }
+    }
+    if chars.next().is_some() {
+        return None;
+    }
+    Some((base, params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_names() {
+        let names: Vec<String> = Matrix::new("add")
+            .param("x", vec![1, 2])
+            .param("y", vec!["a", "b"])
+            .tests(|_| ())
+            .iter()
+            .map(|test| test.desc().name().to_owned())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "add[x=1,y=a]",
+                "add[x=1,y=b]",
+                "add[x=2,y=a]",
+                "add[x=2,y=b]",
+            ]
+        );
+    }
+
+    #[test]
+    fn filter() {
+        let params = |params: &[(&str, &str)]| -> Vec<(String, String)> {
+            params
+                .iter()
+                .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+                .collect()
+        };
+        assert_eq!(
+            parse_filter("add[x=1, y=a]"),
+            Some(("add", params(&[("x", "1"), ("y", "a")])))
+        );
+        assert_eq!(parse_filter("add[]"), Some(("add", vec![])));
+        assert_eq!(parse_filter("add"), None);
+        assert_eq!(parse_filter("add[x]"), None);
+        assert_eq!(parse_filter("add[x=1=2]"), None);
+        assert_eq!(parse_filter("add[x=1]y"), None);
+        assert_eq!(parse_filter("add[x=1\\]"), None);
+    }
+
+    #[test]
+    fn escaped_values() {
+        let tests = Matrix::new("join")
+            .param("sep", vec![",", "=", "]", "\\"])
+            .tests(|_| ());
+        let names: Vec<&str> = tests.iter().map(|test| test.desc().name()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "join[sep=\\,]",
+                "join[sep=\\=]",
+                "join[sep=\\]]",
+                "join[sep=\\\\]"
+            ]
+        );
+
+        // The escaped names are parsed back into the original values.
+        for (test, name) in tests.iter().zip(names) {
+            let (base, params) = parse_filter(name).unwrap();
+            assert_eq!(base, "join");
+            assert_eq!(params.len(), 1);
+            assert_eq!(test.desc().params().get(&params[0].0), Some(&*params[0].1));
+        }
+    }
+}
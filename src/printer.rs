@@ -1,30 +1,42 @@
 use crate::{
-    args::{Args, ColorConfig, OutputFormat},
-    test::{Outcome, OutcomeKind, TestDesc, TestKind},
+    args::{ColorConfig, OutputFormat},
+    bench::BenchStats,
+    json,
+    test::{Metric, Outcome, OutcomeKind, TestDesc, TestKind},
 };
 use console::{Style, StyledObject, Term};
-use std::io::Write;
+use num_format::{Locale, ToFormattedString};
+use std::{cell::Cell, fmt, io::Write, time::Duration};
+
+/// The maximum number of characters per line in the terse format.
+const TERSE_MAX_COLUMN: usize = 88;
 
 pub(crate) struct Printer {
     term: Term,
     format: OutputFormat,
     style: Style,
+    num_tests: Cell<usize>,
+    num_completed: Cell<usize>,
+    column: Cell<usize>,
 }
 
 impl Printer {
-    pub(crate) fn new(args: &Args) -> Self {
+    pub(crate) fn new(color: ColorConfig, format: OutputFormat) -> Self {
         Self {
             term: Term::buffered_stdout(),
-            format: args.format,
+            format,
             style: {
                 let mut style = Style::new();
-                match args.color {
+                match color {
                     ColorConfig::Always => style = style.force_styling(true),
                     ColorConfig::Never => style = style.force_styling(false),
                     _ => (),
                 }
                 style
             },
+            num_tests: Cell::new(0),
+            num_completed: Cell::new(0),
+            column: Cell::new(0),
         }
     }
 
@@ -32,10 +44,74 @@ impl Printer {
         &self.term
     }
 
+    pub(crate) fn format(&self) -> OutputFormat {
+        self.format
+    }
+
     pub(crate) fn styled<D>(&self, val: D) -> StyledObject<D> {
         self.style.apply_to(val)
     }
 
+    pub(crate) fn print_warning(&self, msg: impl fmt::Display) {
+        let _ = self.term.flush();
+        eprintln!("{}: {}", self.styled("warning").yellow(), msg);
+    }
+
+    pub(crate) fn print_error(&self, msg: impl fmt::Display) {
+        let _ = self.term.flush();
+        eprintln!("{}: {}", self.styled("error").red(), msg);
+    }
+
+    pub(crate) fn print_slow_warning(&self, desc: &TestDesc, threshold: Duration) {
+        match self.format {
+            OutputFormat::Json => {
+                let event = json::Object::new()
+                    .string("type", "test")
+                    .string("event", "timeout")
+                    .string("name", desc.name())
+                    .finish();
+                let _ = writeln!(&self.term, "{}", event);
+            }
+            _ => {
+                self.finish_progress();
+                let _ = writeln!(
+                    &self.term,
+                    "test {} has been running for over {} seconds",
+                    desc.name(),
+                    threshold.as_secs()
+                );
+            }
+        }
+        let _ = self.term.flush();
+    }
+
+    pub(crate) fn print_stalled(&self, idle: Duration, running: &[(TestDesc, Duration)]) {
+        if self.format == OutputFormat::Json {
+            // Keep the stdout parsable as JSON lines.
+            let _ = self.term.flush();
+            eprintln!(
+                "no test has completed for {} seconds; still running:",
+                idle.as_secs()
+            );
+            for (desc, elapsed) in running {
+                eprintln!("    {} ({}s)", desc.name(), elapsed.as_secs());
+            }
+            return;
+        }
+
+        self.finish_progress();
+        let _ = writeln!(
+            &self.term,
+            "{}: no test has completed for {} seconds; still running:",
+            self.styled("warning").yellow(),
+            idle.as_secs()
+        );
+        for (desc, elapsed) in running {
+            let _ = writeln!(&self.term, "    {} ({}s)", desc.name(), elapsed.as_secs());
+        }
+        let _ = self.term.flush();
+    }
+
     pub(crate) fn print_list(&self, tests: impl IntoIterator<Item = impl AsRef<TestDesc>>) {
         let quiet = self.format == crate::args::OutputFormat::Terse;
 
@@ -79,6 +155,25 @@ impl Printer {
         }
     }
 
+    pub(crate) fn print_suite_start(&self, num_tests: usize) {
+        self.num_tests.set(num_tests);
+        self.num_completed.set(0);
+        self.column.set(0);
+        match self.format {
+            OutputFormat::Json => {
+                let event = json::Object::new()
+                    .string("type", "suite")
+                    .string("event", "started")
+                    .number("test_count", num_tests)
+                    .finish();
+                let _ = writeln!(&self.term, "{}", event);
+            }
+            _ => {
+                let _ = writeln!(&self.term, "running {} tests", num_tests);
+            }
+        }
+    }
+
     pub(crate) fn print_result(
         &self,
         desc: &TestDesc,
@@ -87,11 +182,8 @@ impl Printer {
     ) {
         match self.format {
             OutputFormat::Pretty => self.print_result_pretty(desc, name_length, outcome),
-            OutputFormat::Terse => self.print_result_terse(desc, name_length, outcome),
-            OutputFormat::Json => eprintln!(
-                "{warning}: JSON format is not supported",
-                warning = self.styled("warning").yellow()
-            ),
+            OutputFormat::Terse => self.print_result_terse(outcome),
+            OutputFormat::Json => self.print_result_json(desc, outcome),
         }
     }
 
@@ -118,16 +210,23 @@ impl Printer {
                         self.styled("FAILED").red()
                     );
                 }
-                OutcomeKind::Measured { average, variance } => {
+                OutcomeKind::Measured(stats) => {
                     let _ = writeln!(
                         &self.term,
                         "test {0:<1$} ... {2}: {3:>11} ns/iter (+/- {4})",
                         name,
                         name_length,
                         self.styled("bench").cyan(),
-                        average,
-                        variance
+                        grouped(stats.average),
+                        grouped(stats.variance)
                     );
+                    if stats.samples > 0 {
+                        let _ = writeln!(
+                            &self.term,
+                            "     {}",
+                            self.styled(format_stats(stats)).dim()
+                        );
+                    }
                 }
             },
             None => {
@@ -140,22 +239,182 @@ impl Printer {
                 );
             }
         }
+        for metric in outcome.map_or(&[][..], |o| o.metrics()) {
+            let _ = writeln!(
+                &self.term,
+                "     {}: {} {}",
+                metric.name,
+                grouped_float(metric.value),
+                metric.unit
+            );
+        }
         let _ = self.term.flush();
     }
 
-    fn print_result_terse(&self, desc: &TestDesc, name_length: usize, outcome: Option<&Outcome>) {
-        let ch = match outcome {
-            Some(o) => match o.kind() {
-                OutcomeKind::Passed => ".",
-                OutcomeKind::Failed => "F",
-                OutcomeKind::Measured { .. } => {
-                    // benchmark test does not support terse format.
-                    return self.print_result_pretty(desc, name_length, outcome);
-                }
-            },
-            None => "i",
+    fn print_result_terse(&self, outcome: Option<&Outcome>) {
+        let ch = match outcome.map(Outcome::kind) {
+            Some(OutcomeKind::Passed) => self.styled("."),
+            Some(OutcomeKind::Failed) => self.styled("F").red(),
+            Some(OutcomeKind::Measured(..)) => self.styled("b").cyan(),
+            None => self.styled("i").yellow(),
         };
-        let _ = self.term.write_str(ch);
+        let _ = write!(&self.term, "{}", ch);
+
+        // Wrap the line with the progress counter, as well as libtest does.
+        let num_completed = self.num_completed.get() + 1;
+        self.num_completed.set(num_completed);
+        self.column.set(self.column.get() + 1);
+        if self.column.get() == TERSE_MAX_COLUMN {
+            self.column.set(0);
+            let _ = writeln!(&self.term, " {}/{}", num_completed, self.num_tests.get());
+        }
+        let _ = self.term.flush();
+    }
+
+    /// Terminate the line of progress characters printed in the terse format.
+    pub(crate) fn finish_progress(&self) {
+        if self.format == OutputFormat::Terse && self.column.replace(0) > 0 {
+            let _ = writeln!(&self.term);
+        }
+    }
+
+    /// Print the benchmark results in the compact form, used in the terse format.
+    pub(crate) fn print_bench_results_terse(&self, measured: &[(TestDesc, BenchStats)]) {
+        let name_length = measured
+            .iter()
+            .map(|(desc, _)| desc.name().len())
+            .max()
+            .unwrap_or(0);
+
+        let _ = writeln!(&self.term);
+        let _ = writeln!(&self.term, "benchmarks:");
+        for (desc, stats) in measured {
+            let _ = writeln!(
+                &self.term,
+                "    {0:<1$} {2:>11} ns/iter (+/- {3})",
+                desc.name(),
+                name_length,
+                grouped(stats.average),
+                grouped(stats.variance)
+            );
+        }
+    }
+
+    fn print_result_json(&self, desc: &TestDesc, outcome: Option<&Outcome>) {
+        let _ = writeln!(&self.term, "{}", result_to_json(desc, outcome).finish());
         let _ = self.term.flush();
     }
 }
+
+pub(crate) fn grouped(n: u64) -> String {
+    n.to_formatted_string(&Locale::en)
+}
+
+/// Format the floating-point value with the digit grouping, rounding to two decimal places.
+fn grouped_float(value: f64) -> String {
+    if !value.is_finite() || value.abs() >= i64::MAX as f64 {
+        return value.to_string();
+    }
+    let rounded = (value * 100.0).round() / 100.0;
+    let int = rounded.trunc() as i64;
+    let frac = (rounded.fract().abs() * 100.0).round() as u64;
+    let sign = if rounded < 0.0 && int == 0 { "-" } else { "" };
+    match frac {
+        0 => format!("{}{}", sign, int.to_formatted_string(&Locale::en)),
+        frac => format!(
+            "{}{}.{:02}",
+            sign,
+            int.to_formatted_string(&Locale::en),
+            frac
+        ),
+    }
+}
+
+pub(crate) fn result_to_json(desc: &TestDesc, outcome: Option<&Outcome>) -> json::Object {
+    let name = desc.name();
+    let event = match outcome.map(|o| (o, o.kind())) {
+        Some((_, OutcomeKind::Passed)) => json::Object::new()
+            .string("type", "test")
+            .string("event", "ok")
+            .string("name", name),
+        Some((outcome, OutcomeKind::Failed)) => {
+            let mut event = json::Object::new()
+                .string("type", "test")
+                .string("event", "failed")
+                .string("name", name);
+            if let Some(msg) = outcome.err_msg() {
+                event = event.string("message", &msg);
+            }
+            if let Some(mismatch) = outcome.mismatched() {
+                event = event
+                    .string("expected", &mismatch.expected)
+                    .string("actual", &mismatch.actual);
+            }
+            if !outcome.artifacts().is_empty() {
+                let paths: Vec<_> = outcome
+                    .artifacts()
+                    .iter()
+                    .map(|path| path.to_string_lossy())
+                    .collect();
+                event = event.strings("artifacts", paths.iter().map(|path| &**path));
+            }
+            event
+        }
+        // The statistics of the samples are omitted if the benchmark only reported
+        // the average by `Outcome::measured`.
+        Some((_, OutcomeKind::Measured(stats))) if stats.samples == 0 => json::Object::new()
+            .string("type", "bench")
+            .string("name", name)
+            .number("deviation", stats.variance)
+            .number("samples", stats.samples)
+            .number("average", stats.average),
+        Some((_, OutcomeKind::Measured(stats))) => json::Object::new()
+            .string("type", "bench")
+            .string("name", name)
+            .number("median", stats.median)
+            .number("deviation", stats.variance)
+            .number("samples", stats.samples)
+            .number("average", stats.average)
+            .number("min", stats.min)
+            .number("max", stats.max)
+            .number("p25", stats.p25)
+            .number("p75", stats.p75)
+            .number("p90", stats.p90)
+            .number("p99", stats.p99)
+            .number("outliers_low", stats.outliers_low)
+            .number("outliers_high", stats.outliers_high),
+        None => json::Object::new()
+            .string("type", "test")
+            .string("event", "ignored")
+            .string("name", name),
+    };
+    let metrics = outcome.map_or(&[][..], |o| o.metrics());
+    if metrics.is_empty() {
+        event
+    } else {
+        event.objects("metrics", metrics.iter().map(metric_to_json))
+    }
+}
+
+fn metric_to_json(metric: &Metric) -> json::Object {
+    json::Object::new()
+        .string("name", &metric.name)
+        .float("value", metric.value)
+        .string("unit", &metric.unit)
+}
+
+fn format_stats(stats: &BenchStats) -> String {
+    format!(
+        "[min {min}, p25 {p25}, median {median}, p75 {p75}, p90 {p90}, p99 {p99}, max {max}] \
+         {samples} samples, {outliers} outliers",
+        min = grouped(stats.min),
+        p25 = grouped(stats.p25),
+        median = grouped(stats.median),
+        p75 = grouped(stats.p75),
+        p90 = grouped(stats.p90),
+        p99 = grouped(stats.p99),
+        max = grouped(stats.max),
+        samples = stats.samples,
+        outliers = stats.outliers_low + stats.outliers_high,
+    )
+}
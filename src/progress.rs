@@ -0,0 +1,246 @@
+use crate::{
+    args::{Args, OutputFormat},
+    printer::Printer,
+    report::Report,
+    reporter::Reporter,
+    test::{Outcome, OutcomeKind, TestDesc},
+};
+use console::truncate_str;
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    io::Write,
+    time::{Duration, Instant},
+};
+
+/// The maximum number of running tests displayed in the status area.
+const MAX_DISPLAYED_RUNNING: usize = 3;
+
+/// The interval of redrawing the status area.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The interval of checking slow tests when the status area is not drawn.
+const CHECK_SLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+struct Running {
+    desc: TestDesc,
+    started: Instant,
+    warned: bool,
+}
+
+/// The tracker of running tests, which also passes the events to the reporter.
+///
+/// When the live display is enabled, the status area that shows the
+/// number of passed, failed and running tests is drawn below the test
+/// results and updated each time the state of tests changes.
+///
+/// It also warns about the tests running longer than the threshold given by
+/// `--warn-slow`, and lists the running tests if no test has completed
+/// for the same duration.
+pub(crate) struct Progress<'a> {
+    printer: &'a Printer,
+    reporter: RefCell<&'a mut dyn Reporter>,
+    live: bool,
+    warn_slow: Option<Duration>,
+    running: RefCell<Vec<Running>>,
+    passed: Cell<usize>,
+    failed: Cell<usize>,
+    idle_since: Cell<Instant>,
+    status_lines: Cell<usize>,
+}
+
+impl<'a> Progress<'a> {
+    /// The status area is drawn only if `allow_live` is set, since the other
+    /// reporters may not write the results to the same terminal.
+    pub(crate) fn new(
+        args: &Args,
+        printer: &'a Printer,
+        reporter: &'a mut dyn Reporter,
+        allow_live: bool,
+    ) -> Self {
+        Self {
+            printer,
+            reporter: RefCell::new(reporter),
+            live: allow_live
+                && args.progress
+                && printer.format() == OutputFormat::Pretty
+                && printer.term().is_term(),
+            warn_slow: args.warn_slow,
+            running: RefCell::new(vec![]),
+            passed: Cell::new(0),
+            failed: Cell::new(0),
+            idle_since: Cell::new(Instant::now()),
+            status_lines: Cell::new(0),
+        }
+    }
+
+    pub(crate) fn num_failed(&self) -> usize {
+        self.failed.get()
+    }
+
+    /// Return the interval of calling `tick`, or `None` if it is not necessary.
+    pub(crate) fn tick_interval(&self) -> Option<Duration> {
+        if self.live {
+            Some(REFRESH_INTERVAL)
+        } else if self.warn_slow.is_some() {
+            Some(CHECK_SLOW_INTERVAL)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn suite_started(&self, tests: &[TestDesc]) {
+        self.reporter.borrow_mut().suite_start(tests);
+    }
+
+    pub(crate) fn test_started(&self, desc: &TestDesc) {
+        self.reporter.borrow_mut().test_start(desc);
+        self.running.borrow_mut().push(Running {
+            desc: desc.clone(),
+            started: Instant::now(),
+            warned: false,
+        });
+    }
+
+    /// Record the completion of the test, and return its execution time.
+    pub(crate) fn test_finished(
+        &self,
+        desc: &TestDesc,
+        outcome: Option<&Outcome>,
+    ) -> Option<Duration> {
+        self.clear_status();
+        self.reporter.borrow_mut().test_result(desc, outcome);
+
+        let elapsed = {
+            let mut running = self.running.borrow_mut();
+            running
+                .iter()
+                .position(|running| running.desc.name() == desc.name())
+                .map(|i| running.remove(i).started.elapsed())
+        };
+        self.idle_since.set(Instant::now());
+        match outcome.map(Outcome::kind) {
+            Some(OutcomeKind::Passed) | Some(OutcomeKind::Measured(..)) => {
+                self.passed.set(self.passed.get() + 1)
+            }
+            Some(OutcomeKind::Failed) => self.failed.set(self.failed.get() + 1),
+            None => (),
+        }
+
+        self.draw_status();
+        elapsed
+    }
+
+    /// Check the slow tests and redraw the status area.
+    pub(crate) fn tick(&self) {
+        if let Some(threshold) = self.warn_slow {
+            self.check_slow(threshold);
+        }
+        self.clear_status();
+        self.draw_status();
+    }
+
+    fn check_slow(&self, threshold: Duration) {
+        let mut slow_tests = vec![];
+        let mut stalled = None;
+        {
+            let mut running = self.running.borrow_mut();
+            for test in running.iter_mut() {
+                if !test.warned && test.started.elapsed() >= threshold {
+                    test.warned = true;
+                    slow_tests.push(test.desc.clone());
+                }
+            }
+
+            // The running tests are listed only if they have not been just warned.
+            let idle = self.idle_since.get().elapsed();
+            if idle >= threshold && slow_tests.is_empty() && !running.is_empty() {
+                self.idle_since.set(Instant::now());
+                let tests: Vec<_> = running
+                    .iter()
+                    .map(|test| (test.desc.clone(), test.started.elapsed()))
+                    .collect();
+                stalled = Some((idle, tests));
+            }
+        }
+
+        if slow_tests.is_empty() && stalled.is_none() {
+            return;
+        }
+
+        self.clear_status();
+        for desc in &slow_tests {
+            self.printer.print_slow_warning(desc, threshold);
+        }
+        if let Some((idle, tests)) = stalled {
+            self.printer.print_stalled(idle, &tests);
+        }
+    }
+
+    pub(crate) fn warning(&self, msg: impl fmt::Display) {
+        self.clear_status();
+        self.reporter.borrow_mut().warning(&msg);
+        self.draw_status();
+    }
+
+    pub(crate) fn error(&self, msg: impl fmt::Display) {
+        self.clear_status();
+        self.reporter.borrow_mut().error(&msg);
+    }
+
+    /// Erase the status area before printing the summary.
+    pub(crate) fn finish(&self) {
+        self.clear_status();
+    }
+
+    pub(crate) fn summary(&self, report: &Report) {
+        self.reporter.borrow_mut().summary(report);
+    }
+
+    fn clear_status(&self) {
+        let lines = self.status_lines.replace(0);
+        if lines > 0 {
+            // Flush before the reporter writes the results to its own buffer.
+            let _ = self.printer.term().clear_last_lines(lines);
+            let _ = self.printer.term().flush();
+        }
+    }
+
+    fn draw_status(&self) {
+        if !self.live {
+            return;
+        }
+
+        let running = self.running.borrow();
+        let mut term = self.printer.term();
+        let (_, width) = term.size();
+        let width = usize::from(width).saturating_sub(1);
+
+        let _ = writeln!(
+            term,
+            "{}: {} passed; {} failed; {} running",
+            self.printer.styled("progress").cyan(),
+            self.passed.get(),
+            self.failed.get(),
+            running.len(),
+        );
+        // The tests are pushed in the order of starting, so the longest-running
+        // ones are at the beginning.
+        let mut lines = 1;
+        for test in running.iter().take(MAX_DISPLAYED_RUNNING) {
+            let line = format!(
+                "    {} ({}s)",
+                test.desc.name(),
+                test.started.elapsed().as_secs()
+            );
+            let _ = writeln!(
+                term,
+                "{}",
+                self.printer.styled(truncate_str(&line, width, "...")).dim()
+            );
+            lines += 1;
+        }
+        self.status_lines.set(lines);
+        let _ = term.flush();
+    }
+}
@@ -0,0 +1,56 @@
+//! The registry of test cases defined by `#[mimicaw::test]`.
+
+use crate::{case::TestCase, test::Test};
+
+/// An entry of the registry submitted by `#[mimicaw::test]`.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct Registration {
+    factory: fn() -> Test<TestCase>,
+}
+
+impl Registration {
+    #[doc(hidden)]
+    pub const fn new(factory: fn() -> Test<TestCase>) -> Self {
+        Self { factory }
+    }
+}
+
+inventory::collect!(Registration);
+
+/// Return the name of the test function `ident` defined in the module `module_path`.
+///
+/// The crate name is stripped from the path, as in the test names of libtest.
+#[doc(hidden)]
+pub fn test_name(module_path: &str, ident: &str) -> String {
+    match module_path.find("::") {
+        Some(i) => format!("{}::{}", &module_path[i + 2..], ident),
+        None => ident.to_owned(),
+    }
+}
+
+/// Return the test cases defined by `#[mimicaw::test]` anywhere in the binary.
+///
+/// The test cases are sorted by their names, so the order does not depend on
+/// the layout of the binary. This allows the test binaries with
+/// `harness = false` to discover the test cases on stable Rust.
+pub fn collected_tests() -> Vec<Test<TestCase>> {
+    let mut tests: Vec<_> = inventory::iter::<Registration>
+        .into_iter()
+        .map(|registration| (registration.factory)())
+        .collect();
+    tests.sort_by(|a, b| a.desc().name().cmp(b.desc().name()));
+    tests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(test_name("krate", "foo"), "foo");
+        assert_eq!(test_name("krate::a", "foo"), "a::foo");
+        assert_eq!(test_name("krate::a::b", "foo"), "a::b::foo");
+    }
+}
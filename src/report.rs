@@ -1,7 +1,17 @@
-use crate::{printer::Printer, test::TestDesc, ExitStatus};
+use crate::{
+    args::OutputFormat,
+    baseline::BenchChange,
+    bench::BenchStats,
+    diff::{self, Line},
+    json,
+    printer::{self, Printer},
+    test::{Metric, Mismatch, TestDesc},
+    ExitStatus,
+};
 use std::{
     borrow::Cow,
     io::{self, Write},
+    path::PathBuf,
     sync::Arc,
 };
 
@@ -12,52 +22,161 @@ pub struct Report {
     /// Passed test cases.
     pub passed: Vec<TestDesc>,
 
-    /// Failed test cases with the error messages.
-    pub failed: Vec<(TestDesc, Option<Arc<Cow<'static, str>>>)>,
+    /// Failed test cases with the details of the failures.
+    pub failed: Vec<(TestDesc, Failure)>,
 
     /// Benchmark results.
-    pub measured: Vec<(TestDesc, (u64, u64))>,
+    pub measured: Vec<(TestDesc, BenchStats)>,
 
     /// Test cases skipped because they do not satisfy the execution conditions.
     pub ignored: Vec<TestDesc>,
 
     /// Test cases filtered out.
     pub filtered_out: Vec<TestDesc>,
+
+    pub(crate) baseline_changes: Vec<(TestDesc, BenchChange)>,
+    pub(crate) metrics: Vec<(TestDesc, Vec<Metric>)>,
+    pub(crate) cancelled: Vec<TestDesc>,
+    pub(crate) not_run: Vec<TestDesc>,
+    pub(crate) stopped: Option<StopReason>,
+    pub(crate) snapshots_updated: usize,
+}
+
+/// The details of a failed test case.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct Failure {
+    /// The error message.
+    pub message: Option<Arc<Cow<'static, str>>>,
+
+    /// The expected and actual values given to `Outcome::mismatch`.
+    pub mismatch: Option<Arc<Mismatch>>,
+
+    /// The paths of the saved attachments.
+    pub artifacts: Vec<PathBuf>,
+}
+
+/// The reason why the test run was stopped.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum StopReason {
+    /// The number of failed test cases reached the limit given by
+    /// `--max-fail` or `--fail-fast`.
+    MaxFail(usize),
+
+    /// The test run was interrupted by Ctrl-C.
+    Interrupted,
 }
 
 impl Report {
     /// Return an exit status used as a result of the test process.
+    ///
+    /// The status is regarded as a failure if there are failed test cases
+    /// or benchmarks that regressed from the baseline.
+    /// If the test run was interrupted, the distinct status is returned.
     pub fn status(&self) -> ExitStatus {
-        if self.failed.is_empty() {
+        if self.stopped == Some(StopReason::Interrupted) {
+            return ExitStatus::INTERRUPTED;
+        }
+        if self.failed.is_empty() && !self.regressed() {
             ExitStatus::OK
         } else {
             ExitStatus::FAILED
         }
     }
 
+    /// Return the benchmark results compared with the baseline specified by `--baseline`.
+    #[inline]
+    pub fn baseline_changes(&self) -> &[(TestDesc, BenchChange)] {
+        &self.baseline_changes
+    }
+
+    /// Return the custom metrics reported by test cases.
+    #[inline]
+    pub fn metrics(&self) -> &[(TestDesc, Vec<Metric>)] {
+        &self.metrics
+    }
+
+    /// Return the test cases that were running when the test run was stopped.
+    #[inline]
+    pub fn cancelled(&self) -> &[TestDesc] {
+        &self.cancelled
+    }
+
+    /// Return the test cases that were not started because the test run was stopped.
+    #[inline]
+    pub fn not_run(&self) -> &[TestDesc] {
+        &self.not_run
+    }
+
+    /// Return the reason why the test run was stopped before all test cases completed.
+    #[inline]
+    pub fn stopped(&self) -> Option<StopReason> {
+        self.stopped
+    }
+
+    /// Return the number of snapshots rewritten by `--bless`.
+    #[inline]
+    pub fn snapshots_updated(&self) -> usize {
+        self.snapshots_updated
+    }
+
+    fn regressed(&self) -> bool {
+        self.baseline_changes
+            .iter()
+            .any(|(_, change)| change.regressed)
+    }
+
     /// Return an iterator of skipped test cases.
     #[inline]
     pub fn skipped(&self) -> impl Iterator<Item = (&TestDesc, &str)> + '_ {
         let ignored = self.ignored.iter().map(|desc| (desc, "ignored"));
         let filtered_out = self.filtered_out.iter().map(|desc| (desc, "filtered out"));
-        ignored.chain(filtered_out)
+        let cancelled = self.cancelled.iter().map(|desc| (desc, "cancelled"));
+        let not_run = self.not_run.iter().map(|desc| (desc, "not run"));
+        ignored.chain(filtered_out).chain(cancelled).chain(not_run)
     }
 
     pub(crate) fn print(&self, printer: &Printer) -> io::Result<()> {
-        let mut status = printer.styled("ok").green();
+        if printer.format() == OutputFormat::Json {
+            return self.print_json(printer);
+        }
+
+        printer.finish_progress();
+        if printer.format() == OutputFormat::Terse && !self.measured.is_empty() {
+            printer.print_bench_results_terse(&self.measured);
+        }
+
+        if !self.baseline_changes.is_empty() {
+            self.print_baseline_changes(printer)?;
+        }
+
+        let status = if self.status().success() {
+            printer.styled("ok").green()
+        } else {
+            printer.styled("FAILED").red()
+        };
 
         if !self.failed.is_empty() {
-            status = printer.styled("FAILED").red();
             writeln!(printer.term())?;
             writeln!(printer.term(), "failures:")?;
-            for (desc, msg) in &self.failed {
+            for (desc, failure) in &self.failed {
                 writeln!(printer.term(), "---- {} ----", desc.name())?;
-                if let Some(msg) = msg {
+                if let Some(ref msg) = failure.message {
                     write!(printer.term(), "{}", msg)?;
-                    if msg.chars().last().map_or(true, |c| c != '\n') {
+                    if !msg.ends_with('\n') {
                         writeln!(printer.term())?;
                     }
                 }
+                if let Some(ref mismatch) = failure.mismatch {
+                    print_mismatch(printer, mismatch)?;
+                }
+                if !failure.artifacts.is_empty() {
+                    writeln!(printer.term(), "artifacts:")?;
+                    for path in &failure.artifacts {
+                        writeln!(printer.term(), "    {}", path.display())?;
+                    }
+                }
             }
 
             writeln!(printer.term())?;
@@ -67,6 +186,17 @@ impl Report {
             }
         }
 
+        if !self.cancelled.is_empty() {
+            writeln!(printer.term())?;
+            match self.stopped {
+                Some(StopReason::Interrupted) => writeln!(printer.term(), "interrupted:")?,
+                _ => writeln!(printer.term(), "cancelled:")?,
+            }
+            for desc in &self.cancelled {
+                writeln!(printer.term(), "    {}", desc.name())?;
+            }
+        }
+
         writeln!(printer.term())?;
         writeln!(printer.term(), "test result: {status}. {passed} passed; {failed} failed; {ignored} ignored; {measured} measured; {filtered_out} filtered out",
             status = status,
@@ -77,6 +207,175 @@ impl Report {
             filtered_out = self.filtered_out.len(),
         )?;
 
+        if self.snapshots_updated > 0 {
+            writeln!(
+                printer.term(),
+                "{}: {} updated",
+                printer.styled("note").yellow(),
+                plural(self.snapshots_updated, "snapshot"),
+            )?;
+        }
+
+        if let Some(reason) = self.stopped {
+            let reason = match reason {
+                StopReason::MaxFail(1) => "after the first failure".to_owned(),
+                StopReason::MaxFail(n) => format!("after {} failures", n),
+                StopReason::Interrupted => "by Ctrl-C".to_owned(),
+            };
+            let mut counts = vec![];
+            if !self.cancelled.is_empty() {
+                counts.push(format!(
+                    "{} cancelled",
+                    plural(self.cancelled.len(), "test")
+                ));
+            }
+            if !self.not_run.is_empty() {
+                counts.push(format!("{} not run", plural(self.not_run.len(), "test")));
+            }
+            write!(
+                printer.term(),
+                "{}: the test run was stopped {}",
+                printer.styled("note").yellow(),
+                reason,
+            )?;
+            if !counts.is_empty() {
+                write!(printer.term(), "; {}", counts.join(", "))?;
+            }
+            writeln!(printer.term())?;
+        }
+
+        printer.term().flush()
+    }
+
+    fn print_baseline_changes(&self, printer: &Printer) -> io::Result<()> {
+        let name_length = self
+            .baseline_changes
+            .iter()
+            .map(|(desc, _)| desc.name().len())
+            .max()
+            .unwrap_or(0);
+
+        writeln!(printer.term())?;
+        writeln!(printer.term(), "benchmark changes:")?;
+        for (desc, change) in &self.baseline_changes {
+            let percent = format!("{:+.2}%", change.percent);
+            let percent = if change.regressed {
+                printer.styled(percent).red()
+            } else if change.percent < 0.0 {
+                printer.styled(percent).green()
+            } else {
+                printer.styled(percent)
+            };
+            write!(
+                printer.term(),
+                "    {0:<1$} {2:>9} ({3} -> {4} ns/iter)",
+                desc.name(),
+                name_length,
+                percent,
+                printer::grouped(change.baseline),
+                printer::grouped(change.current),
+            )?;
+            if change.regressed {
+                write!(printer.term(), " {}", printer.styled("regressed").red())?;
+            }
+            writeln!(printer.term())?;
+        }
         Ok(())
     }
+
+    fn print_json(&self, printer: &Printer) -> io::Result<()> {
+        for (desc, change) in &self.baseline_changes {
+            let event = json::Object::new()
+                .string("type", "bench_change")
+                .string("name", desc.name())
+                .number("baseline", change.baseline)
+                .number("current", change.current)
+                .float("percent", change.percent)
+                .number("regressed", change.regressed)
+                .finish();
+            writeln!(printer.term(), "{}", event)?;
+        }
+
+        let event = json::Object::new()
+            .string("type", "suite")
+            .string(
+                "event",
+                if self.status().success() {
+                    "ok"
+                } else {
+                    "failed"
+                },
+            )
+            .number("passed", self.passed.len())
+            .number("failed", self.failed.len())
+            .number("ignored", self.ignored.len())
+            .number("measured", self.measured.len())
+            .number("filtered_out", self.filtered_out.len());
+        let event = if self.snapshots_updated > 0 {
+            event.number("snapshots_updated", self.snapshots_updated)
+        } else {
+            event
+        };
+        let event = match self.stopped {
+            Some(reason) => event
+                .number("cancelled", self.cancelled.len())
+                .number("not_run", self.not_run.len())
+                .string(
+                    "stopped",
+                    match reason {
+                        StopReason::MaxFail(..) => "max_fail",
+                        StopReason::Interrupted => "interrupted",
+                    },
+                ),
+            None => event,
+        }
+        .finish();
+        writeln!(printer.term(), "{}", event)?;
+        printer.term().flush()
+    }
+}
+
+/// Format the count with the noun, which is pluralized unless the count is one.
+fn plural(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("{} {}", n, noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+fn print_mismatch(printer: &Printer, mismatch: &Mismatch) -> io::Result<()> {
+    writeln!(
+        printer.term(),
+        "diff ({} expected, {} actual):",
+        printer.styled("-").red(),
+        printer.styled("+").green(),
+    )?;
+    let lines = diff::diff_with_context(&mismatch.expected, &mismatch.actual);
+    if lines.iter().all(|line| line.is_none()) {
+        // The diff is line-based, so it does not show the trailing newline or `\r`.
+        let note = if mismatch.expected.ends_with('\n') != mismatch.actual.ends_with('\n') {
+            "the values differ only in the trailing newline"
+        } else {
+            "the values differ only in line endings"
+        };
+        return writeln!(printer.term(), "  ({})", note);
+    }
+    for line in lines {
+        match line {
+            Some(Line::Same(text)) => writeln!(printer.term(), "  {}", text)?,
+            Some(Line::Removed(text)) => writeln!(
+                printer.term(),
+                "{}",
+                printer.styled(format!("- {}", text)).red()
+            )?,
+            Some(Line::Added(text)) => writeln!(
+                printer.term(),
+                "{}",
+                printer.styled(format!("+ {}", text)).green()
+            )?,
+            None => writeln!(printer.term(), "  ...")?,
+        }
+    }
+    Ok(())
 }
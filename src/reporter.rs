@@ -0,0 +1,148 @@
+use crate::{
+    args::{Args, OutputFormat},
+    printer::Printer,
+    report::Report,
+    test::{Outcome, TestDesc},
+};
+use std::{fmt, rc::Rc};
+
+/// The receiver of the events during a test run.
+///
+/// The results are written to the console by `PrettyReporter`, `TerseReporter`
+/// or `JsonReporter` selected by `--format`, unless another reporter is given
+/// to `run_tests_with_reporter`.
+pub trait Reporter {
+    /// Called before running the tests, with the tests that passed the filters.
+    fn suite_start(&mut self, tests: &[TestDesc]);
+
+    /// Called when the test starts running. The ignored tests are not started.
+    fn test_start(&mut self, _desc: &TestDesc) {}
+
+    /// Called when the test completes, with `None` if the test is ignored.
+    fn test_result(&mut self, desc: &TestDesc, outcome: Option<&Outcome>);
+
+    /// Called after all the tests have completed, or the test run has stopped.
+    fn summary(&mut self, report: &Report);
+
+    /// Called when the driver warns about something other than the test results,
+    /// such as a baseline that failed to load.
+    ///
+    /// The warnings about slow or stalled tests are not passed here, since they are
+    /// written to the console along with the progress display selected by `--format`.
+    ///
+    /// The default implementation writes the message to stderr.
+    fn warning(&mut self, msg: &dyn fmt::Display) {
+        eprintln!("warning: {}", msg);
+    }
+
+    /// Called when the driver aborts the test run before running the tests,
+    /// such as on duplicated test names or the shard timings that failed to load.
+    ///
+    /// The default implementation writes the message to stderr.
+    fn error(&mut self, msg: &dyn fmt::Display) {
+        eprintln!("error: {}", msg);
+    }
+}
+
+/// The built-in formats writing to stdout.
+struct Console {
+    printer: Rc<Printer>,
+    name_length: usize,
+}
+
+impl Console {
+    fn new(args: &Args, format: OutputFormat) -> Self {
+        Self::with_printer(Rc::new(Printer::new(args.color, format)))
+    }
+
+    fn with_printer(printer: Rc<Printer>) -> Self {
+        Self {
+            printer,
+            name_length: 0,
+        }
+    }
+
+    fn suite_start(&mut self, tests: &[TestDesc]) {
+        self.name_length = tests
+            .iter()
+            .map(|desc| desc.name().len())
+            .max()
+            .unwrap_or(0);
+        self.printer.print_suite_start(tests.len());
+    }
+
+    fn test_result(&mut self, desc: &TestDesc, outcome: Option<&Outcome>) {
+        self.printer.print_result(desc, self.name_length, outcome);
+    }
+
+    fn summary(&mut self, report: &Report) {
+        let _ = report.print(&self.printer);
+    }
+
+    fn warning(&mut self, msg: &dyn fmt::Display) {
+        self.printer.print_warning(msg);
+    }
+
+    fn error(&mut self, msg: &dyn fmt::Display) {
+        self.printer.print_error(msg);
+    }
+}
+
+macro_rules! console_reporters {
+    ($($(#[$attr:meta])* $name:ident => $format:ident,)*) => {$(
+        $(#[$attr])*
+        pub struct $name(Console);
+
+        impl $name {
+            /// Create the reporter with the options given on the command line, such as `--color`.
+            pub fn new(args: &Args) -> Self {
+                Self(Console::new(args, OutputFormat::$format))
+            }
+        }
+
+        impl Reporter for $name {
+            fn suite_start(&mut self, tests: &[TestDesc]) {
+                self.0.suite_start(tests)
+            }
+
+            fn test_result(&mut self, desc: &TestDesc, outcome: Option<&Outcome>) {
+                self.0.test_result(desc, outcome)
+            }
+
+            fn summary(&mut self, report: &Report) {
+                self.0.summary(report)
+            }
+
+            fn warning(&mut self, msg: &dyn fmt::Display) {
+                self.0.warning(msg)
+            }
+
+            fn error(&mut self, msg: &dyn fmt::Display) {
+                self.0.error(msg)
+            }
+        }
+    )*};
+}
+
+console_reporters! {
+    /// The reporter that writes a line per test and the details of failures,
+    /// like the default output of libtest.
+    PrettyReporter => Pretty,
+
+    /// The reporter that writes a character per test, like `--format=terse`.
+    TerseReporter => Terse,
+
+    /// The reporter that writes a JSON object per line, like `--format=json`.
+    JsonReporter => Json,
+}
+
+/// Create the reporter selected by `--format`, sharing the printer with the driver
+/// so that the warnings and the status area are interleaved correctly.
+pub(crate) fn from_format(printer: Rc<Printer>) -> Box<dyn Reporter> {
+    let console = Console::with_printer(printer.clone());
+    match printer.format() {
+        OutputFormat::Pretty => Box::new(PrettyReporter(console)),
+        OutputFormat::Terse => Box::new(TerseReporter(console)),
+        OutputFormat::Json => Box::new(JsonReporter(console)),
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+/// A partition of the test suite selected by `--shard=INDEX/TOTAL`.
+#[derive(Debug)]
+pub(crate) struct Shard {
+    index: usize,
+    total: usize,
+    timings: Option<HashMap<String, Duration>>,
+}
+
+impl Shard {
+    /// Create a shard with the 1-based index.
+    pub(crate) fn new(index: usize, total: usize) -> Self {
+        debug_assert!(1 <= index && index <= total);
+        Self {
+            index: index - 1,
+            total,
+            timings: None,
+        }
+    }
+
+    /// Use the execution time of tests for balancing the shards.
+    pub(crate) fn with_timings(self, path: &Path) -> io::Result<Self> {
+        let timings = load_timings(path)?;
+        Ok(Self {
+            timings: Some(timings),
+            ..self
+        })
+    }
+
+    /// Return whether each test belongs to this shard.
+    ///
+    /// The result is determined only from the set of test names
+    /// (and the timings), so every shard agrees on the partition.
+    pub(crate) fn select(&self, names: &[&str]) -> Vec<bool> {
+        let timings = match self.timings {
+            Some(ref timings) => timings,
+            None => {
+                return names
+                    .iter()
+                    .map(|name| fnv1a(name) % self.total as u64 == self.index as u64)
+                    .collect();
+            }
+        };
+
+        // Assign the tests with the known timing in the descending order of
+        // the execution time, to the shard with the smallest total.
+        // The tests without timing are assigned by the hash of their names.
+        let mut timed: Vec<(usize, Duration)> = names
+            .iter()
+            .enumerate()
+            .filter_map(|(i, name)| Some((i, *timings.get(*name)?)))
+            .collect();
+        timed.sort_by(|&(i, a), &(j, b)| b.cmp(&a).then_with(|| names[i].cmp(names[j])));
+
+        let mut selected: Vec<bool> = names
+            .iter()
+            .map(|name| {
+                !timings.contains_key(*name) && fnv1a(name) % self.total as u64 == self.index as u64
+            })
+            .collect();
+        let mut loads = vec![Duration::from_secs(0); self.total];
+        for (i, elapsed) in timed {
+            let (shard, _) = loads
+                .iter()
+                .enumerate()
+                .min_by_key(|&(shard, load)| (*load, shard))
+                .expect("at least one shard");
+            loads[shard] += elapsed;
+            selected[i] = shard == self.index;
+        }
+        selected
+    }
+}
+
+/// The 64-bit FNV-1a hash, used as the stable hash of test names.
+pub(crate) fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Load the execution time of tests.
+///
+/// Each line of the file consists of the test name and the execution time
+/// in seconds separated by a tab character.
+fn load_timings(path: &Path) -> io::Result<HashMap<String, Duration>> {
+    let content = fs::read_to_string(path)?;
+    let mut timings = HashMap::new();
+    for (lineno, line) in content.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.rsplitn(2, '\t');
+        let secs = fields.next().and_then(|secs| secs.parse::<f64>().ok());
+        match (fields.next(), secs) {
+            (Some(name), Some(secs)) if secs.is_finite() && secs >= 0.0 => {
+                timings.insert(name.to_owned(), Duration::from_secs_f64(secs));
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid timing at line {}", lineno + 1),
+                ));
+            }
+        }
+    }
+    Ok(timings)
+}
+
+/// Save the execution time of tests in the format read by `--shard-timings`.
+pub(crate) fn save_timings<'a>(
+    path: &Path,
+    timings: impl IntoIterator<Item = (&'a str, Duration)>,
+) -> io::Result<()> {
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    for (name, elapsed) in timings {
+        writeln!(file, "{}\t{:.6}", name, elapsed.as_secs_f64())?;
+    }
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partitions(total: usize, timings: Option<HashMap<String, Duration>>) -> Vec<Vec<bool>> {
+        let names: Vec<String> = (0..100).map(|i| format!("test_{}", i)).collect();
+        let names: Vec<&str> = names.iter().map(|s| s.as_str()).collect();
+        (1..=total)
+            .map(|index| {
+                let shard = Shard {
+                    timings: timings.clone(),
+                    ..Shard::new(index, total)
+                };
+                shard.select(&names)
+            })
+            .collect()
+    }
+
+    fn assert_disjoint_cover(partitions: &[Vec<bool>]) {
+        for i in 0..partitions[0].len() {
+            let count = partitions.iter().filter(|p| p[i]).count();
+            assert_eq!(count, 1, "test_{} is selected by {} shards", i, count);
+        }
+    }
+
+    #[test]
+    fn partition_by_hash() {
+        let partitions = partitions(3, None);
+        assert_disjoint_cover(&partitions);
+        for p in &partitions {
+            assert!(p.iter().any(|&selected| selected));
+        }
+    }
+
+    #[test]
+    fn partition_by_timings() {
+        let timings = (0..50)
+            .map(|i| (format!("test_{}", i), Duration::from_secs(i)))
+            .collect();
+        let partitions = partitions(4, Some(timings));
+        assert_disjoint_cover(&partitions);
+
+        let loads: Vec<u64> = partitions
+            .iter()
+            .map(|p| (0..50).filter(|&i| p[i as usize]).sum())
+            .collect();
+        let max = loads.iter().max().unwrap();
+        let min = loads.iter().min().unwrap();
+        assert!(max - min <= 49, "unbalanced loads: {:?}", loads);
+    }
+}
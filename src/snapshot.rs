@@ -0,0 +1,85 @@
+use crate::{fixture, test::Outcome};
+use std::{
+    fs, io,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// The settings of snapshot testing, shared by all the tests in a run.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SnapshotOptions {
+    bless: bool,
+    updated: Arc<AtomicUsize>,
+}
+
+impl SnapshotOptions {
+    pub(crate) fn new(bless: bool) -> Self {
+        Self {
+            bless,
+            updated: Arc::default(),
+        }
+    }
+
+    /// Return the number of snapshots rewritten by `--bless`.
+    pub(crate) fn updated(&self) -> usize {
+        self.updated.load(Ordering::SeqCst)
+    }
+
+    /// Count the snapshots rewritten in the child processes run by `--isolate`.
+    pub(crate) fn add_updated(&self, n: usize) {
+        self.updated.fetch_add(n, Ordering::SeqCst);
+    }
+
+    pub(crate) fn check(&self, path: &Path, actual: &str) -> Outcome {
+        let path = fixture::resolve_path(path);
+        let expected = match fs::read_to_string(&path) {
+            Ok(expected) => Some(expected),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Outcome::failed().error_message(format!(
+                    "failed to read the snapshot {}: {}",
+                    path.display(),
+                    err
+                ))
+            }
+        };
+        if expected.as_deref() == Some(actual) {
+            return Outcome::passed();
+        }
+
+        if self.bless {
+            return match write_snapshot(&path, actual) {
+                Ok(()) => {
+                    self.updated.fetch_add(1, Ordering::SeqCst);
+                    Outcome::passed()
+                }
+                Err(err) => Outcome::failed().error_message(format!(
+                    "failed to write the snapshot {}: {}",
+                    path.display(),
+                    err
+                )),
+            };
+        }
+
+        match expected {
+            Some(expected) => Outcome::mismatch(expected, actual).error_message(format!(
+                "the snapshot {} does not match (run with --bless to update it)",
+                path.display(),
+            )),
+            None => Outcome::failed().error_message(format!(
+                "the snapshot {} does not exist (run with --bless to create it)",
+                path.display()
+            )),
+        }
+    }
+}
+
+fn write_snapshot(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)
+}
@@ -0,0 +1,93 @@
+use crate::{driver::TestRunner, test::Outcome, TestDesc};
+use futures_core::future::Future;
+use futures_util::future::FutureExt;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+use futures_util::future::{self, AbortHandle, Abortable};
+use std::{any::Any, panic::AssertUnwindSafe};
+
+/// An executor that runs the test cases on its own worker threads.
+///
+/// The driver polls all the test cases on the task awaiting `run_tests`,
+/// so the CPU-bound test cases are not run in parallel unless they are
+/// spawned onto the executor with `SpawnRunner`.
+pub trait Spawner {
+    /// The handle of the spawned test case, resolved to its outcome.
+    type Handle: Future<Output = Outcome>;
+
+    /// Spawn a test case onto the executor.
+    fn spawn<F>(&self, future: F) -> Self::Handle
+    where
+        F: Future<Output = Outcome> + Send + 'static;
+}
+
+/// A test runner that spawns each test case onto a `Spawner`.
+///
+/// The panics in the test cases are caught and reported as failures.
+#[derive(Debug)]
+pub struct SpawnRunner<S, R> {
+    spawner: S,
+    runner: R,
+}
+
+impl<S, R> SpawnRunner<S, R> {
+    /// Create a test runner that spawns the futures returned from `runner`.
+    pub fn new(spawner: S, runner: R) -> Self {
+        Self { spawner, runner }
+    }
+}
+
+impl<D, S, R> TestRunner<D> for SpawnRunner<S, R>
+where
+    S: Spawner,
+    R: TestRunner<D>,
+    R::Future: Send + 'static,
+{
+    type Future = S::Handle;
+
+    fn run(&mut self, desc: TestDesc, data: D) -> Self::Future {
+        let test_case = AssertUnwindSafe(self.runner.run(desc, data))
+            .catch_unwind()
+            .map(|result| result.unwrap_or_else(panicked));
+        self.spawner.spawn(test_case)
+    }
+}
+
+/// Make the test case abortable, so that it stops running on the executor
+/// when the handle holding the returned guard is dropped.
+///
+/// The driver drops the handles of the running test cases when the test run
+/// is stopped, e.g. by `--fail-fast` or Ctrl-C.
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub(crate) fn abort_on_drop<F: Future>(future: F) -> (Abortable<F>, AbortOnDrop) {
+    let (future, handle) = future::abortable(future);
+    (future, AbortOnDrop(handle))
+}
+
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+#[derive(Debug)]
+pub(crate) struct AbortOnDrop(AbortHandle);
+
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Convert the payload of a panic into the failed outcome.
+pub(crate) fn panicked(payload: Box<dyn Any + Send>) -> Outcome {
+    Outcome::failed().error_message(format!(
+        "the test case panicked: {}",
+        panic_message(&*payload)
+    ))
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    match payload.downcast_ref::<&'static str>() {
+        Some(msg) => msg,
+        None => match payload.downcast_ref::<String>() {
+            Some(msg) => msg,
+            None => "Box<Any>",
+        },
+    }
+}
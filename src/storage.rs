@@ -0,0 +1,28 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+/// Return the directory where mimicaw stores the persistent data, such as benchmark baselines.
+///
+/// The directory is placed under the target directory of Cargo, that is
+/// `$CARGO_TARGET_DIR` or the one inferred from the location of the test binary.
+pub(crate) fn data_dir() -> PathBuf {
+    target_dir().join("mimicaw")
+}
+
+fn target_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("CARGO_TARGET_DIR") {
+        return dir.into();
+    }
+
+    // The test binaries built by Cargo are located at `target/<profile>/deps`
+    // or `target/<profile>/examples`.
+    env::current_exe()
+        .ok()
+        .as_deref()
+        .and_then(Path::parent)
+        .filter(|dir| dir.ends_with("deps") || dir.ends_with("examples"))
+        .and_then(|dir| dir.parent()?.parent())
+        .map_or_else(|| PathBuf::from("target"), Path::to_path_buf)
+}
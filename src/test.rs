@@ -1,4 +1,18 @@
-use std::{borrow::Cow, sync::Arc};
+use crate::{
+    bench::{BenchOptions, BenchStats, Bencher},
+    fixture,
+    params::Params,
+    snapshot::SnapshotOptions,
+};
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{Debug, Display, Write as _},
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 #[derive(Copy, Clone, Debug)]
 pub(crate) enum TestKind {
@@ -15,6 +29,12 @@ struct TestDescInner {
     name: Cow<'static, str>,
     kind: TestKind,
     ignored: bool,
+    tags: Vec<Cow<'static, str>>,
+    file: Option<PathBuf>,
+    base_name: Option<Cow<'static, str>>,
+    params: Params,
+    bench_options: BenchOptions,
+    snapshot_options: SnapshotOptions,
 }
 
 impl AsRef<Self> for TestDesc {
@@ -31,16 +51,13 @@ impl TestDesc {
     /// Return the name of test.
     #[inline]
     pub fn name(&self) -> &str {
-        &*self.0.name
+        &self.0.name
     }
 
     /// Return whether the test is a benchmark or not.
     #[inline]
     pub fn is_bench(&self) -> bool {
-        match self.0.kind {
-            TestKind::Bench => true,
-            _ => false,
-        }
+        matches!(self.0.kind, TestKind::Bench)
     }
 
     /// Return whether the test should be ignored or not.
@@ -48,6 +65,72 @@ impl TestDesc {
     pub fn ignored(&self) -> bool {
         self.0.ignored
     }
+
+    /// Return the tags attached to the test.
+    #[inline]
+    pub fn tags(&self) -> &[Cow<'static, str>] {
+        &self.0.tags
+    }
+
+    /// Return the parameters of the test generated by `Test::cases` or `Matrix`.
+    #[inline]
+    pub fn params(&self) -> &Params {
+        &self.0.params
+    }
+
+    /// Return the name of the parameterized test without the parameters.
+    pub(crate) fn base_name(&self) -> Option<&str> {
+        self.0.base_name.as_deref()
+    }
+
+    /// Return the path of the fixture file that the test was created from.
+    #[inline]
+    pub fn file(&self) -> Option<&Path> {
+        self.0.file.as_deref()
+    }
+
+    /// Return whether the benchmark is run only once as a test.
+    ///
+    /// This is the case unless `--bench` is specified.
+    /// The outcome of such benchmarks is reported as passed or failed
+    /// rather than measured.
+    #[inline]
+    pub fn is_smoke_test(&self) -> bool {
+        self.is_bench() && self.0.bench_options.smoke_test
+    }
+
+    /// Create a `Bencher` for measuring the performance of this benchmark.
+    pub fn bencher(&self) -> Bencher {
+        Bencher::new(self.0.bench_options)
+    }
+
+    /// Compare the actual value with the snapshot stored in the file.
+    ///
+    /// The relative path is resolved from the manifest directory of the package.
+    /// If they differ, the failed outcome with the diff is returned.
+    /// When `--bless` is specified or `MIMICAW_BLESS=1` is set, the snapshot
+    /// is rewritten with the actual value instead.
+    pub fn check_snapshot(&self, path: impl AsRef<Path>, actual: impl AsRef<str>) -> Outcome {
+        self.0
+            .snapshot_options
+            .check(path.as_ref(), actual.as_ref())
+    }
+
+    fn inner_mut(&mut self) -> &mut TestDescInner {
+        Arc::get_mut(&mut self.0).expect("the test description has already been shared")
+    }
+
+    pub(crate) fn set_bench_options(&mut self, bench_options: BenchOptions) {
+        self.inner_mut().bench_options = bench_options;
+    }
+
+    pub(crate) fn snapshot_options(&self) -> &SnapshotOptions {
+        &self.0.snapshot_options
+    }
+
+    pub(crate) fn set_snapshot_options(&mut self, snapshot_options: SnapshotOptions) {
+        self.inner_mut().snapshot_options = snapshot_options;
+    }
 }
 
 /// Data that describes a single test.
@@ -58,6 +141,7 @@ pub struct Test<D> {
 
 impl<D> Test<D> {
     /// Create a single test.
+    #[allow(clippy::self_named_constructors)]
     pub fn test(name: impl Into<Cow<'static, str>>, data: D) -> Self {
         Self::new(name.into(), TestKind::Test, data)
     }
@@ -73,14 +157,87 @@ impl<D> Test<D> {
                 name,
                 kind,
                 ignored: false,
+                tags: vec![],
+                file: None,
+                base_name: None,
+                params: Params::default(),
+                bench_options: BenchOptions::default(),
+                snapshot_options: SnapshotOptions::default(),
             })),
             data,
         }
     }
 
+    /// Create a test for each value of the parameter, named like `base[param=value]`.
+    ///
+    /// The value is used as the data of the test, and is also available
+    /// from `TestDesc::params` formatted as a string.
+    pub fn cases(
+        base: impl Into<Cow<'static, str>>,
+        param: impl Into<Cow<'static, str>>,
+        values: impl IntoIterator<Item = D>,
+    ) -> Vec<Self>
+    where
+        D: Display,
+    {
+        let base = base.into();
+        let param = param.into();
+        values
+            .into_iter()
+            .map(|value| {
+                let params = Params(vec![(param.clone(), value.to_string())]);
+                Self::with_params(base.clone(), params, value)
+            })
+            .collect()
+    }
+
+    pub(crate) fn rename(&mut self, name: String) {
+        self.desc.inner_mut().name = name.into();
+    }
+
+    pub(crate) fn with_params(base: Cow<'static, str>, params: Params, data: D) -> Self {
+        let mut test = Self::test(params.test_name(&base), data);
+        let inner = test.desc.inner_mut();
+        inner.base_name = Some(base);
+        inner.params = params;
+        test
+    }
+
+    /// Create a test for each file under `dir` whose path matches the glob pattern.
+    ///
+    /// The relative `dir` is resolved from the manifest directory of the package.
+    /// Each test is named after the path of the file relative to `dir`,
+    /// separated by `/`, and the data is created from the path of the file
+    /// by `f`. The path is also available from `TestDesc::file`.
+    ///
+    /// In the pattern, `*` and `?` match within a path segment, and `**`
+    /// matches any number of directories, e.g. `**/*.json`.
+    pub fn from_dir(
+        dir: impl AsRef<Path>,
+        pattern: &str,
+        mut f: impl FnMut(&Path) -> D,
+    ) -> io::Result<Vec<Self>> {
+        let dir = fixture::resolve_path(dir.as_ref());
+        let files = fixture::find_files(&dir, pattern)?;
+        Ok(files
+            .into_iter()
+            .map(|(name, path)| {
+                let mut test = Self::test(name, f(&path));
+                test.desc.inner_mut().file = Some(path);
+                test
+            })
+            .collect())
+    }
+
     /// Mark that this test should be ignored.
     pub fn ignore(mut self, value: bool) -> Self {
-        Arc::get_mut(&mut self.desc.0).unwrap().ignored = value;
+        self.desc.inner_mut().ignored = value;
+        self
+    }
+
+    /// Attach a tag to this test, such as `slow` or `network`.
+    pub fn tag(mut self, tag: impl Into<Cow<'static, str>>) -> Self {
+        self.desc.inner_mut().tags.push(tag.into());
         self
     }
 
@@ -98,6 +255,10 @@ impl<D> Test<D> {
 pub struct Outcome {
     kind: OutcomeKind,
     err_msg: Option<Arc<Cow<'static, str>>>,
+    mismatch: Option<Arc<Mismatch>>,
+    metrics: Vec<Metric>,
+    attachments: Vec<(Cow<'static, str>, Attachment)>,
+    artifacts: Vec<PathBuf>,
 }
 
 impl Outcome {
@@ -106,6 +267,10 @@ impl Outcome {
         Self {
             kind,
             err_msg: None,
+            mismatch: None,
+            metrics: vec![],
+            attachments: vec![],
+            artifacts: vec![],
         }
     }
 
@@ -120,9 +285,65 @@ impl Outcome {
         Self::new(OutcomeKind::Failed)
     }
 
+    /// Create an `Outcome` representing that the test failed because the actual value
+    /// did not match the expected one.
+    ///
+    /// The values are displayed as a line diff in the summary, and are preserved
+    /// in the JSON output.
+    pub fn mismatch(expected: impl Into<String>, actual: impl Into<String>) -> Self {
+        Self {
+            mismatch: Some(Arc::new(Mismatch {
+                expected: expected.into(),
+                actual: actual.into(),
+            })),
+            ..Self::failed().error_message("the actual value did not match the expected value")
+        }
+    }
+
+    /// Create an `Outcome` representing that the test failed with the error.
+    ///
+    /// The error message includes the chain of `source()`. No backtrace is
+    /// included: the backtrace captured by the error itself can only be retrieved
+    /// through the generic member access of `Error`, which is not available on
+    /// stable Rust, and capturing one here would only point to this conversion
+    /// rather than the origin of the error.
+    pub fn from_error(err: impl Into<Box<dyn Error>>) -> Self {
+        let err = err.into();
+        let mut msg = err.to_string();
+        let mut causes = std::iter::successors(err.source(), |&err| err.source()).peekable();
+        if causes.peek().is_some() {
+            msg += "\n\nCaused by:";
+            for (i, cause) in causes.enumerate() {
+                let _ = write!(msg, "\n    {}: {}", i, cause);
+            }
+        }
+        Self::failed().error_message(msg)
+    }
+
+    /// Compare the values, and create the passed `Outcome` if they are equal.
+    ///
+    /// Otherwise, the outcome is `Outcome::mismatch` with the values formatted
+    /// by `{:#?}`. Use `Outcome::mismatch` directly for comparing texts line by line.
+    pub fn compare<T>(expected: &T, actual: &T) -> Self
+    where
+        T: PartialEq + Debug + ?Sized,
+    {
+        if expected == actual {
+            Self::passed()
+        } else {
+            Self::mismatch(format!("{:#?}", expected), format!("{:#?}", actual))
+        }
+    }
+
     /// Create an `Outcome` representing that the benchmark test was successfully run.
     pub fn measured(average: u64, variance: u64) -> Self {
-        Self::new(OutcomeKind::Measured { average, variance })
+        Self::measured_with_stats(BenchStats::from_average(average, variance))
+    }
+
+    /// Create an `Outcome` representing that the benchmark test was successfully run,
+    /// with the detailed statistics of the samples.
+    pub fn measured_with_stats(stats: BenchStats) -> Self {
+        Self::new(OutcomeKind::Measured(stats))
     }
 
     /// Specify the error message.
@@ -133,18 +354,261 @@ impl Outcome {
         }
     }
 
-    pub(crate) fn kind(&self) -> &OutcomeKind {
+    /// Append a custom metric, such as the number of handled requests.
+    pub fn metric(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: f64,
+        unit: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.metrics.push(Metric {
+            name: name.into(),
+            value,
+            unit: unit.into(),
+        });
+        self
+    }
+
+    /// Append the throughput metric calculated from the number of bytes
+    /// processed within the specified duration.
+    pub fn throughput_bytes(self, bytes: u64, elapsed: Duration) -> Self {
+        let value = bytes as f64 / elapsed.as_secs_f64();
+        self.metric("throughput", value, "B/s")
+    }
+
+    /// Append the throughput metric calculated from the number of elements
+    /// processed within the specified duration.
+    pub fn throughput_elements(self, elements: u64, elapsed: Duration) -> Self {
+        let value = elements as f64 / elapsed.as_secs_f64();
+        self.metric("throughput", value, "elements/s")
+    }
+
+    /// Attach a file or bytes, such as logs and dumps, for investigating the failure.
+    ///
+    /// If the test fails, the attachment is saved as `NAME` in the directory of the test
+    /// under `--artifacts-dir`, and listed in the summary and the JSON output.
+    /// The characters not suitable for file names are replaced with `_`, in which case
+    /// a hash of the original name is added to keep it unique. An attachment with the same
+    /// name as an earlier one replaces it. The attachments of passed tests are discarded.
+    pub fn attach(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        attachment: impl Into<Attachment>,
+    ) -> Self {
+        let name = name.into();
+        self.attachments.retain(|(attached, _)| *attached != name);
+        self.attachments.push((name, attachment.into()));
+        self
+    }
+
+    /// Discard the benchmark result, regarding it as passed.
+    pub(crate) fn into_smoke_result(self) -> Self {
+        match self.kind {
+            OutcomeKind::Measured(..) => Self {
+                kind: OutcomeKind::Passed,
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// Return the kind of outcome.
+    pub fn kind(&self) -> &OutcomeKind {
         &self.kind
     }
 
+    /// Return the error message.
+    pub fn message(&self) -> Option<&str> {
+        self.err_msg.as_deref().map(|msg| &**msg)
+    }
+
+    /// Return the expected and actual values given by `Outcome::mismatch`.
+    pub fn mismatched(&self) -> Option<&Mismatch> {
+        self.mismatch.as_deref()
+    }
+
+    /// Return the custom metrics.
+    pub fn metrics(&self) -> &[Metric] {
+        &self.metrics
+    }
+
+    /// Return the paths of the attachments saved into the artifacts directory.
+    ///
+    /// This is empty until the test completes, and for the passed tests.
+    pub fn artifacts(&self) -> &[PathBuf] {
+        &self.artifacts
+    }
+
     pub(crate) fn err_msg(&self) -> Option<Arc<Cow<'static, str>>> {
         self.err_msg.clone()
     }
+
+    pub(crate) fn shared_mismatch(&self) -> Option<Arc<Mismatch>> {
+        self.mismatch.clone()
+    }
+
+    pub(crate) fn take_attachments(&mut self) -> Vec<(Cow<'static, str>, Attachment)> {
+        std::mem::take(&mut self.attachments)
+    }
+
+    pub(crate) fn add_artifact(&mut self, path: PathBuf) {
+        self.artifacts.push(path);
+    }
+
+    /// Append a line to the error message.
+    pub(crate) fn append_error_message(&mut self, line: impl Display) {
+        let msg = match self.err_msg.take() {
+            Some(msg) => format!("{}\n{}", msg.trim_end_matches('\n'), line),
+            None => line.to_string(),
+        };
+        self.err_msg = Some(Arc::new(msg.into()));
+    }
+}
+
+/// The test bodies returning nothing are regarded as passed when they complete.
+impl From<()> for Outcome {
+    fn from(_: ()) -> Self {
+        Self::passed()
+    }
+}
+
+/// The test bodies returning `Result` are regarded as passed when they return `Ok(())`,
+/// and failed with `Outcome::from_error` otherwise.
+impl<E> From<Result<(), E>> for Outcome
+where
+    E: Into<Box<dyn Error>>,
+{
+    fn from(result: Result<(), E>) -> Self {
+        match result {
+            Ok(()) => Self::passed(),
+            Err(err) => Self::from_error(err),
+        }
+    }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub(crate) enum OutcomeKind {
+/// The expected and actual values of a test failed by `Outcome::mismatch`.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Mismatch {
+    /// The expected value.
+    pub expected: String,
+
+    /// The actual value.
+    pub actual: String,
+}
+
+/// The content attached to the outcome of a test by `Outcome::attach`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Attachment {
+    /// The file copied into the artifacts directory.
+    File(PathBuf),
+
+    /// The bytes written into the artifacts directory.
+    Bytes(Vec<u8>),
+}
+
+impl From<PathBuf> for Attachment {
+    fn from(path: PathBuf) -> Self {
+        Attachment::File(path)
+    }
+}
+
+impl From<&Path> for Attachment {
+    fn from(path: &Path) -> Self {
+        Attachment::File(path.to_path_buf())
+    }
+}
+
+impl From<Vec<u8>> for Attachment {
+    fn from(bytes: Vec<u8>) -> Self {
+        Attachment::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for Attachment {
+    fn from(bytes: &[u8]) -> Self {
+        Attachment::Bytes(bytes.to_vec())
+    }
+}
+
+/// A custom metric reported with the outcome of a test.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Metric {
+    /// The name of metric.
+    pub name: Cow<'static, str>,
+
+    /// The measured value.
+    pub value: f64,
+
+    /// The unit of value, such as `B/s`.
+    pub unit: Cow<'static, str>,
+}
+
+/// The kind of `Outcome`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum OutcomeKind {
+    /// The test passed.
     Passed,
+
+    /// The test or benchmark failed.
     Failed,
-    Measured { average: u64, variance: u64 },
+
+    /// The benchmark was measured.
+    Measured(BenchStats),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Chained(&'static str, Option<Box<Chained>>);
+
+    impl fmt::Display for Chained {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    impl Error for Chained {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            self.1.as_deref().map(|err| err as _)
+        }
+    }
+
+    #[test]
+    fn error_with_sources() {
+        let err = Chained(
+            "failed to load the config",
+            Some(Box::new(Chained(
+                "failed to read config.toml",
+                Some(Box::new(Chained("permission denied", None))),
+            ))),
+        );
+        let outcome = Outcome::from_error(err);
+        assert!(matches!(outcome.kind(), OutcomeKind::Failed));
+        assert_eq!(
+            outcome.err_msg().as_deref().map(|msg| &**msg),
+            Some(
+                "failed to load the config\n\
+                 \n\
+                 Caused by:\n    \
+                 0: failed to read config.toml\n    \
+                 1: permission denied"
+            )
+        );
+    }
+
+    #[test]
+    fn error_without_sources() {
+        let outcome = Outcome::from(Err::<(), _>(Chained("failed", None)));
+        assert_eq!(
+            outcome.err_msg().as_deref().map(|msg| &**msg),
+            Some("failed")
+        );
+    }
 }
@@ -0,0 +1,108 @@
+//! Integration with the tokio runtime.
+
+use crate::{
+    args::Args,
+    driver::TestRunner,
+    spawn::{self, AbortOnDrop, Spawner},
+    test::{Outcome, Test},
+    ExitStatus,
+};
+use ::tokio::{runtime::Runtime, task::JoinHandle};
+use futures_core::{
+    future::Future,
+    task::{self, Poll},
+};
+use futures_util::{future::Aborted, ready};
+use std::pin::Pin;
+
+/// A `Spawner` that spawns the test cases onto the current tokio runtime.
+#[derive(Debug, Default, Clone)]
+pub struct TokioSpawner {
+    _p: (),
+}
+
+impl TokioSpawner {
+    /// Create a new `TokioSpawner`.
+    ///
+    /// The test cases must be spawned within the context of tokio runtime.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Spawner for TokioSpawner {
+    type Handle = Handle;
+
+    fn spawn<F>(&self, future: F) -> Self::Handle
+    where
+        F: Future<Output = Outcome> + Send + 'static,
+    {
+        let (future, abort) = spawn::abort_on_drop(future);
+        Handle {
+            join: ::tokio::spawn(future),
+            _abort: abort,
+        }
+    }
+}
+
+/// The handle of a test case spawned by `TokioSpawner`.
+///
+/// The test case is aborted when the handle is dropped before completion.
+#[derive(Debug)]
+pub struct Handle {
+    join: JoinHandle<Result<Outcome, Aborted>>,
+    _abort: AbortOnDrop,
+}
+
+impl Future for Handle {
+    type Output = Outcome;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let result = ready!(Pin::new(&mut self.join).poll(cx));
+        Poll::Ready(match result {
+            Ok(Ok(outcome)) => outcome,
+            Ok(Err(Aborted)) => Outcome::failed().error_message("the test task was aborted"),
+            Err(err) => Outcome::failed().error_message(format!("the test task failed: {}", err)),
+        })
+    }
+}
+
+/// Run a test suite on a new tokio runtime and terminate the process.
+///
+/// The runtime uses the threaded scheduler, so the test cases spawned with
+/// `TokioSpawner` are run in parallel.
+pub fn main<D>(tests: impl IntoIterator<Item = Test<D>>, runner: impl TestRunner<D>) -> ! {
+    let args = Args::from_env().unwrap_or_else(|st| st.exit());
+    let mut runtime = Runtime::new().unwrap_or_else(|err| {
+        eprintln!("failed to start the tokio runtime: {}", err);
+        ExitStatus::FAILED.exit()
+    });
+    runtime
+        .block_on(crate::run_tests(&args, tests, runner))
+        .exit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_channel::oneshot;
+    use futures_util::future;
+    use std::time::Duration;
+
+    #[test]
+    fn dropped_handle_aborts_test_case() {
+        let mut runtime = Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (tx, rx) = oneshot::channel::<()>();
+            let handle = TokioSpawner::new().spawn(async move {
+                // The sender is dropped together with the test case.
+                let _tx = tx;
+                future::pending().await
+            });
+            drop(handle);
+
+            let stopped = ::tokio::time::timeout(Duration::from_secs(10), rx).await;
+            assert!(stopped.is_ok(), "the test case is still running");
+        });
+    }
+}
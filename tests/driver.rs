@@ -0,0 +1,639 @@
+//! Tests of the behavior of the test driver.
+//!
+//! Each check runs one of the scenarios, small test suites defined below,
+//! in a child process of this executable and inspects its output.
+
+use futures::executor::block_on;
+use futures_timer::Delay;
+use mimicaw::{Args, ExitStatus, Outcome, OutcomeKind, Report, Reporter, Test, TestDesc};
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+    thread,
+    time::Duration,
+};
+
+/// The environment variable that selects the scenario run by the child process.
+const SCENARIO_ENV: &str = "MIMICAW_TEST_SCENARIO";
+
+type Check = fn() -> Result<(), String>;
+
+fn main() {
+    if let Ok(name) = env::var(SCENARIO_ENV) {
+        let args = Args::from_env().unwrap_or_else(|st| st.exit());
+        match scenario(&name, &args) {
+            Ok(report) => report.status().exit(),
+            Err(status) => status.exit(),
+        }
+    }
+
+    let checks: Vec<Test<Check>> = vec![
+        Test::test("baseline", baseline),
+        Test::test("smoke_test", smoke_test),
+        Test::test("bench_without_samples", bench_without_samples),
+        Test::test("terse_wrapping", terse_wrapping),
+        Test::test("progress_without_terminal", progress_without_terminal),
+        Test::test("slow_warnings", slow_warnings),
+        Test::test("fail_fast", fail_fast),
+        Test::test("shard", shard),
+        Test::test("isolate", isolate),
+        Test::test("snapshot_bless", snapshot_bless),
+        Test::test("artifacts", artifacts),
+        Test::test("reporter_events", reporter_events),
+        // Ctrl-C is simulated by sending SIGINT with kill(1).
+        Test::test("interrupt", interrupt as Check).ignore(cfg!(not(unix))),
+    ];
+    mimicaw::main(checks, |_desc: TestDesc, check: Check| async move {
+        match check() {
+            Ok(()) => Outcome::passed(),
+            Err(msg) => Outcome::failed().error_message(msg),
+        }
+    })
+}
+
+fn scenario(name: &str, args: &Args) -> Result<Report, ExitStatus> {
+    match name {
+        // The benchmarks taking 1000 or 2000 ns/iter.
+        "bench" | "bench_slow" => {
+            let average = if name == "bench" { 1000 } else { 2000 };
+            let tests = vec![Test::bench("sum", ()), Test::test("check", ())];
+            block_on(mimicaw::run_tests_with_report(
+                args,
+                tests,
+                |desc: TestDesc, ()| async move {
+                    if desc.is_bench() {
+                        Outcome::measured(average, 0)
+                    } else {
+                        Outcome::passed()
+                    }
+                },
+            ))
+        }
+        // The passing tests, as many as to wrap the terse output.
+        "many" => {
+            let tests = (0..100).map(|i| Test::test(format!("test_{:03}", i), ()));
+            block_on(mimicaw::run_tests_with_report(
+                args,
+                tests,
+                |_desc, ()| async { Outcome::passed() },
+            ))
+        }
+        // The test completing after 4.5 seconds, and the one completing immediately.
+        "slow" => {
+            let tests = vec![Test::test("slow", 4500), Test::test("fast", 0)];
+            block_on(mimicaw::run_tests_with_report(
+                args,
+                tests,
+                |_desc, millis| async move {
+                    Delay::new(Duration::from_millis(millis)).await;
+                    Outcome::passed()
+                },
+            ))
+        }
+        // The failing test followed by many slow ones.
+        "fail_fast" => {
+            let tests = Some(Test::test("fail", 0))
+                .into_iter()
+                .chain((0..1000).map(|i| Test::test(format!("pass_{:04}", i), 100)));
+            let report = block_on(mimicaw::run_tests_with_report(
+                args,
+                tests,
+                |_desc, millis| async move {
+                    if millis == 0 {
+                        return Outcome::failed();
+                    }
+                    Delay::new(Duration::from_millis(millis)).await;
+                    Outcome::passed()
+                },
+            ))?;
+            println!("cancelled: {}", report.cancelled().len());
+            println!("not run: {}", report.not_run().len());
+            Ok(report)
+        }
+        // The tests run with --isolate, including the one terminating the process.
+        "isolate" => {
+            let tests = vec![
+                Test::test("pass", 0),
+                Test::test("fail", 1),
+                Test::test("exit", 2),
+            ];
+            block_on(mimicaw::run_tests_with_report(
+                args,
+                tests,
+                |_desc, case| async move {
+                    match case {
+                        0 => Outcome::passed(),
+                        1 => Outcome::failed().error_message("reported by the child"),
+                        _ => process::exit(3),
+                    }
+                },
+            ))
+        }
+        // The tests comparing the values with the snapshots in the scratch directory.
+        "snapshot" => {
+            let dir = PathBuf::from(env::var_os("CARGO_TARGET_DIR").unwrap());
+            let tests = vec![Test::test("first", ()), Test::test("second", ())];
+            block_on(mimicaw::run_tests_with_report(
+                args,
+                tests,
+                |desc: TestDesc, ()| {
+                    let path = dir.join("snapshots").join(format!("{}.txt", desc.name()));
+                    async move { desc.check_snapshot(path, "value") }
+                },
+            ))
+        }
+        // The tests with attachments, some of whose names are sanitized into the same string.
+        "artifacts" => {
+            let tests = vec![
+                Test::test("fail/a", false),
+                Test::test("fail_a", false),
+                Test::test("pass", true),
+            ];
+            block_on(mimicaw::run_tests_with_report(
+                args,
+                tests,
+                |desc: TestDesc, pass| async move {
+                    let outcome = if pass {
+                        Outcome::passed()
+                    } else {
+                        Outcome::failed()
+                    };
+                    outcome
+                        .attach("log.txt", format!("{}: log.txt", desc.name()).into_bytes())
+                        .attach(
+                            "dir/log.txt",
+                            format!("{}: dir/log.txt", desc.name()).into_bytes(),
+                        )
+                        .attach(
+                            "dir_log.txt",
+                            format!("{}: dir_log.txt", desc.name()).into_bytes(),
+                        )
+                },
+            ))
+        }
+        // The events passed to a custom reporter, written one per line.
+        "reporter" => {
+            let tests = vec![
+                Test::test("same", true),
+                Test::test("same", false),
+                Test::test("skip", true).ignore(true),
+            ];
+            block_on(mimicaw::run_tests_with_reporter(
+                args,
+                tests,
+                |_desc, pass| async move {
+                    if pass {
+                        Outcome::passed()
+                    } else {
+                        Outcome::failed()
+                    }
+                },
+                &mut Recorder,
+            ))
+        }
+        // The test pressing Ctrl-C, and the one still running at that time.
+        "interrupt" => {
+            let tests = vec![Test::test("wait", 10_000), Test::test("ctrl_c", 0)];
+            block_on(mimicaw::run_tests_with_report(
+                args,
+                tests,
+                |_desc, millis| async move {
+                    if millis == 0 {
+                        ctrl_c();
+                        return Outcome::passed();
+                    }
+                    Delay::new(Duration::from_millis(millis)).await;
+                    Outcome::passed()
+                },
+            ))
+        }
+        // Ctrl-C pressed after the test run has completed.
+        "interrupt_after_run" => {
+            let report = block_on(mimicaw::run_tests_with_report(
+                args,
+                vec![Test::test("pass", ())],
+                |_desc, ()| async { Outcome::passed() },
+            ))?;
+            ctrl_c();
+            thread::sleep(Duration::from_secs(10));
+            Ok(report)
+        }
+        name => panic!("unknown scenario: {}", name),
+    }
+}
+
+/// The reporter that writes the events to stdout.
+struct Recorder;
+
+impl Reporter for Recorder {
+    fn suite_start(&mut self, tests: &[TestDesc]) {
+        let names: Vec<&str> = tests.iter().map(TestDesc::name).collect();
+        println!("suite_start: {}", names.join(", "));
+    }
+
+    fn test_start(&mut self, desc: &TestDesc) {
+        println!("test_start: {}", desc.name());
+    }
+
+    fn test_result(&mut self, desc: &TestDesc, outcome: Option<&Outcome>) {
+        let result = match outcome.map(Outcome::kind) {
+            Some(OutcomeKind::Failed) => "failed",
+            Some(..) => "ok",
+            None => "ignored",
+        };
+        println!("test_result: {} {}", desc.name(), result);
+    }
+
+    fn summary(&mut self, report: &Report) {
+        println!(
+            "summary: {} passed; {} failed",
+            report.passed.len(),
+            report.failed.len()
+        );
+    }
+
+    fn warning(&mut self, msg: &dyn fmt::Display) {
+        println!("warning: {}", msg);
+    }
+
+    fn error(&mut self, msg: &dyn fmt::Display) {
+        println!("error: {}", msg);
+    }
+}
+
+fn ctrl_c() {
+    let status = Command::new("kill")
+        .args(["-INT", &process::id().to_string()])
+        .status()
+        .expect("failed to run kill");
+    assert!(status.success(), "failed to send SIGINT");
+}
+
+/// The exit code and the output of a scenario.
+struct Run {
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+impl Run {
+    fn expect_code(&self, code: i32) -> Result<&Self, String> {
+        if self.code == Some(code) {
+            Ok(self)
+        } else {
+            Err(format!("expected exit code {}\n{}", code, self))
+        }
+    }
+
+    fn expect_stdout(&self, pattern: &str) -> Result<&Self, String> {
+        if self.stdout.contains(pattern) {
+            Ok(self)
+        } else {
+            Err(format!("expected {:?} in stdout\n{}", pattern, self))
+        }
+    }
+
+    fn reject_stdout(&self, pattern: &str) -> Result<&Self, String> {
+        if self.stdout.contains(pattern) {
+            Err(format!("unexpected {:?} in stdout\n{}", pattern, self))
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn expect_stderr(&self, pattern: &str) -> Result<&Self, String> {
+        if self.stderr.contains(pattern) {
+            Ok(self)
+        } else {
+            Err(format!("expected {:?} in stderr\n{}", pattern, self))
+        }
+    }
+}
+
+impl std::fmt::Display for Run {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "exit code: {:?}", self.code)?;
+        writeln!(f, "stdout:\n{}", self.stdout)?;
+        write!(f, "stderr:\n{}", self.stderr)
+    }
+}
+
+/// Run the scenario with the command line arguments.
+///
+/// The data of mimicaw, such as the baselines, are stored in `target_dir`.
+fn run(name: &str, target_dir: &Path, args: &[&str]) -> Result<Run, String> {
+    let exe = env::current_exe().map_err(|err| err.to_string())?;
+    let output = Command::new(exe)
+        .args(args)
+        .env(SCENARIO_ENV, name)
+        .env("CARGO_TARGET_DIR", target_dir)
+        .output()
+        .map_err(|err| format!("failed to run the scenario {}: {}", name, err))?;
+    Ok(Run {
+        code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
+
+/// Create an empty directory dedicated to the check.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn baseline() -> Result<(), String> {
+    let dir = scratch_dir("baseline");
+
+    // Nothing is saved when no benchmark was run.
+    run("bench", &dir, &["--save-baseline", "main"])?.expect_code(0)?;
+    run("bench", &dir, &["--bench", "--baseline", "main"])?
+        .expect_code(0)?
+        .expect_stderr("failed to load the baseline `main`")?;
+
+    run("bench", &dir, &["--bench", "--save-baseline", "main"])?.expect_code(0)?;
+    run("bench", &dir, &["--bench", "--baseline", "main"])?
+        .expect_code(0)?
+        .expect_stdout("+0.00%")?;
+    run(
+        "bench_slow",
+        &dir,
+        &["--bench", "--baseline", "main", "--bench-threshold", "10%"],
+    )?
+    .expect_code(101)?
+    .expect_stdout("+100.00%")?
+    .expect_stdout("regressed")?;
+
+    run("bench", &dir, &["--save-baseline", "../main"])?
+        .expect_code(101)?
+        .expect_stderr("must be a name without path separators")?;
+    run("bench", &dir, &["--bench", "--bench-threshold", "10%"])?
+        .expect_code(101)?
+        .expect_stderr("--bench-threshold requires --baseline")?;
+
+    Ok(())
+}
+
+fn smoke_test() -> Result<(), String> {
+    let dir = scratch_dir("smoke_test");
+
+    for args in &[&[][..], &["--test"]] {
+        run("bench", &dir, args)?
+            .expect_code(0)?
+            .expect_stdout("2 passed; 0 failed; 0 ignored; 0 measured")?;
+    }
+    run("bench", &dir, &["--bench"])?
+        .expect_code(0)?
+        .expect_stdout("0 passed; 0 failed; 1 ignored; 1 measured")?;
+
+    Ok(())
+}
+
+fn bench_without_samples() -> Result<(), String> {
+    let dir = scratch_dir("bench_without_samples");
+
+    // Only the average is known from `Outcome::measured`, also in the child processes.
+    for args in &[
+        &["--bench", "--format=json"][..],
+        &["--bench", "--format=json", "--isolate"],
+    ] {
+        run("bench", &dir, args)?
+            .expect_code(0)?
+            .expect_stdout(r#""deviation": 0, "samples": 0, "average": 1000}"#)?
+            .reject_stdout(r#""median""#)?
+            .reject_stdout(r#""min""#)?;
+    }
+    run("bench", &dir, &["--bench"])?
+        .expect_code(0)?
+        .expect_stdout("1,000 ns/iter (+/- 0)\n")?
+        .reject_stdout("median")?;
+
+    Ok(())
+}
+
+fn terse_wrapping() -> Result<(), String> {
+    let dir = scratch_dir("terse_wrapping");
+
+    let run = run("many", &dir, &["--format=terse"])?;
+    run.expect_code(0)?.expect_stdout(&format!(
+        "\n{} 88/100\n{}\n",
+        ".".repeat(88),
+        ".".repeat(12)
+    ))?;
+
+    Ok(())
+}
+
+fn progress_without_terminal() -> Result<(), String> {
+    let dir = scratch_dir("progress_without_terminal");
+
+    // The status area is not drawn, since the stdout is piped.
+    run("many", &dir, &["--progress"])?
+        .expect_code(0)?
+        .expect_stdout("100 passed; 0 failed")?
+        .reject_stdout("progress:")?;
+
+    Ok(())
+}
+
+fn slow_warnings() -> Result<(), String> {
+    let dir = scratch_dir("slow_warnings");
+
+    // The checks are made every second, so the test running for 4.5 seconds is
+    // warned at 1 or 2 seconds and listed as stalled one or two seconds later.
+    // The durations in the messages depend on the timing and are not compared.
+    run("slow", &dir, &["--warn-slow=1"])?
+        .expect_code(0)?
+        .expect_stdout("test slow has been running for over 1 seconds")?
+        .expect_stdout("no test has completed for ")?
+        .expect_stdout(" seconds; still running:\n    slow (")?;
+    run("slow", &dir, &["--warn-slow=0"])?
+        .expect_code(0)?
+        .expect_stdout("2 passed; 0 failed")?
+        .reject_stdout("has been running")?;
+
+    Ok(())
+}
+
+fn fail_fast() -> Result<(), String> {
+    let dir = scratch_dir("fail_fast");
+
+    let count = |run: &Run, prefix: &str| -> Result<usize, String> {
+        run.stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(prefix)?.parse().ok())
+            .ok_or_else(|| format!("missing the line {:?}\n{}", prefix, run))
+    };
+
+    // All the tests start at once, so the remaining ones are cancelled.
+    for args in &[&["--fail-fast"][..], &["--max-fail=1"][..]] {
+        let run = run("fail_fast", &dir, args)?;
+        run.expect_code(101)?.expect_stdout(
+            "the test run was stopped after the first failure; 1000 tests cancelled\n",
+        )?;
+        if count(&run, "cancelled: ")? != 1000 || count(&run, "not run: ")? != 0 {
+            return Err(format!("unexpected number of tests stopped\n{}", run));
+        }
+    }
+
+    // The isolated tests run as many as CPUs at a time, so the rest are not started.
+    let run = run("fail_fast", &dir, &["--fail-fast", "--isolate"])?;
+    run.expect_code(101)?
+        .expect_stdout("the test run was stopped after the first failure")?;
+    if count(&run, "not run: ")? == 0 {
+        return Err(format!("unexpected number of tests not run\n{}", run));
+    }
+
+    Ok(())
+}
+
+fn shard() -> Result<(), String> {
+    let dir = scratch_dir("shard");
+
+    // Every test is run by exactly one of the shards.
+    let mut names = vec![];
+    for index in 1..=4 {
+        let run = run("many", &dir, &[&format!("--shard={}/4", index)])?;
+        run.expect_code(0)?;
+        names.extend(run.stdout.lines().filter_map(|line| {
+            let name = line.strip_prefix("test ")?.strip_suffix(" ... ok")?;
+            Some(name.trim().to_owned())
+        }));
+    }
+    names.sort();
+    let expected: Vec<String> = (0..100).map(|i| format!("test_{:03}", i)).collect();
+    if names != expected {
+        return Err(format!("the shards ran the tests {:?}", names));
+    }
+
+    run(
+        "many",
+        &dir,
+        &["--shard=2/4", "--shard-timings=missing.txt"],
+    )?
+    .expect_code(101)?
+    .expect_stderr("error: failed to load the timings from missing.txt")?;
+
+    Ok(())
+}
+
+fn isolate() -> Result<(), String> {
+    let dir = scratch_dir("isolate");
+
+    run("isolate", &dir, &["--isolate"])?
+        .expect_code(101)?
+        .expect_stdout("1 passed; 2 failed")?
+        .expect_stdout("---- fail ----\nreported by the child\n")?
+        .expect_stdout("the test process exited with code 3 without reporting the result")?;
+
+    Ok(())
+}
+
+fn snapshot_bless() -> Result<(), String> {
+    let dir = scratch_dir("snapshot_bless");
+
+    run("snapshot", &dir, &[])?
+        .expect_code(101)?
+        .expect_stdout("does not exist (run with --bless to create it)")?;
+
+    // The snapshots updated in the child processes are counted by the parent.
+    run("snapshot", &dir, &["--bless", "--isolate"])?
+        .expect_code(0)?
+        .expect_stdout("2 snapshots updated")?;
+
+    run("snapshot", &dir, &[])?
+        .expect_code(0)?
+        .reject_stdout("updated")?;
+
+    Ok(())
+}
+
+fn artifacts() -> Result<(), String> {
+    let dir = scratch_dir("artifacts");
+
+    let pretty = run("artifacts", &dir, &[])?;
+    pretty.expect_code(101)?;
+    let json = run("artifacts", &dir, &["--format=json"])?;
+    json.expect_code(101)?;
+
+    let mut files = vec![];
+    for entry in fs::read_dir(dir.join("mimicaw/artifacts")).map_err(|err| err.to_string())? {
+        let test_dir = entry.map_err(|err| err.to_string())?.path();
+        for entry in fs::read_dir(&test_dir).map_err(|err| err.to_string())? {
+            files.push(entry.map_err(|err| err.to_string())?.path());
+        }
+    }
+
+    // Only the attachments of the failed tests are saved, without overwriting each other.
+    let mut contents = vec![];
+    for path in &files {
+        let path = path.to_string_lossy();
+        pretty.expect_stdout(&format!("    {}\n", path))?;
+        json.expect_stdout(&format!("\"{}\"", path))?;
+        contents.push(fs::read_to_string(&*path).map_err(|err| err.to_string())?);
+    }
+    contents.sort();
+    let expected = [
+        "fail/a: dir/log.txt",
+        "fail/a: dir_log.txt",
+        "fail/a: log.txt",
+        "fail_a: dir/log.txt",
+        "fail_a: dir_log.txt",
+        "fail_a: log.txt",
+    ];
+    if contents != expected {
+        return Err(format!("unexpected artifacts: {:?}\n{}", contents, pretty));
+    }
+
+    Ok(())
+}
+
+fn reporter_events() -> Result<(), String> {
+    let dir = scratch_dir("reporter_events");
+
+    let run = run("reporter", &dir, &["--duplicates=suffix"])?;
+    run.expect_code(101)?;
+    let expected = "\
+warning: renamed the duplicated test same to same#2
+suite_start: same, same#2, skip
+test_start: same
+test_result: same ok
+test_start: same#2
+test_result: same#2 failed
+test_result: skip ignored
+summary: 1 passed; 1 failed
+";
+    if run.stdout != expected {
+        return Err(format!("expected the events:\n{}\n{}", expected, run));
+    }
+
+    // The errors aborting the test run are also passed to the reporter.
+    let run = self::run("reporter", &dir, &[])?;
+    run.expect_code(101)?;
+    let expected = "\
+error: found 1 duplicated test name(s) (use --duplicates=suffix to rename them):
+    same (at positions 1, 2)
+";
+    if run.stdout != expected {
+        return Err(format!("expected the events:\n{}\n{}", expected, run));
+    }
+
+    Ok(())
+}
+
+fn interrupt() -> Result<(), String> {
+    let dir = scratch_dir("interrupt");
+
+    run("interrupt", &dir, &[])?
+        .expect_code(130)?
+        .expect_stdout("interrupted:\n    wait\n")?
+        // The test sending Ctrl-C may or may not complete before the run is stopped.
+        .expect_stdout("the test run was stopped by Ctrl-C;")?;
+
+    // The process is terminated as usual when no test run is active.
+    run("interrupt_after_run", &dir, &[])?.expect_code(130)?;
+
+    Ok(())
+}
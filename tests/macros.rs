@@ -0,0 +1,204 @@
+//! Tests of the test cases defined by `#[mimicaw::test]`.
+//!
+//! The tests below are collected by `mimicaw::collected_tests` and run by
+//! `DefaultRunner`, and then their order and results are compared with the expectations.
+
+use futures::executor::block_on;
+use futures_timer::Delay;
+use mimicaw::{Args, Bencher, DefaultRunner, Outcome, PrettyReporter, Report, Reporter, TestDesc};
+use std::{io, process, thread, time::Duration};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Expect {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+const EXPECTATIONS: &[(&str, Expect)] = &[
+    ("async_body", Expect::Passed),
+    ("blocking_body", Expect::Passed),
+    ("returns_outcome", Expect::Failed),
+    ("returns_ok", Expect::Passed),
+    ("returns_err", Expect::Failed),
+    ("panics", Expect::Failed),
+    ("should_panic", Expect::Passed),
+    ("should_panic_with", Expect::Passed),
+    ("should_panic_with_other", Expect::Failed),
+    ("async_times_out", Expect::Failed),
+    ("blocking_times_out", Expect::Failed),
+    ("ignored", Expect::Ignored),
+    ("renamed", Expect::Passed),
+    ("nested::in_module", Expect::Passed),
+    ("other::in_module", Expect::Failed),
+    ("bench", Expect::Passed),
+];
+
+#[mimicaw::test]
+async fn async_body() {
+    Delay::new(Duration::from_millis(10)).await;
+}
+
+#[mimicaw::test]
+fn blocking_body() {
+    thread::sleep(Duration::from_millis(10));
+}
+
+#[mimicaw::test]
+fn returns_outcome() -> Outcome {
+    Outcome::failed().error_message("explicit failure")
+}
+
+#[mimicaw::test]
+fn returns_ok() -> io::Result<()> {
+    Ok(())
+}
+
+#[mimicaw::test]
+async fn returns_err() -> io::Result<()> {
+    Err(io::Error::other("explicit error"))
+}
+
+#[mimicaw::test]
+fn panics() {
+    panic!("explicit panic");
+}
+
+#[mimicaw::test(should_panic)]
+async fn should_panic() {
+    panic!("explicit panic");
+}
+
+#[mimicaw::test(should_panic(expected = "explicit"))]
+fn should_panic_with() {
+    panic!("explicit panic");
+}
+
+#[mimicaw::test(should_panic = "explicit")]
+fn should_panic_with_other() {
+    panic!("another panic");
+}
+
+#[mimicaw::test(timeout = 0.1)]
+async fn async_times_out() {
+    Delay::new(Duration::from_secs(10)).await;
+}
+
+#[mimicaw::test(timeout = 0.1)]
+fn blocking_times_out() {
+    thread::sleep(Duration::from_secs(2));
+}
+
+#[mimicaw::test(ignore)]
+async fn ignored() {
+    panic!("the ignored test should not be run");
+}
+
+#[mimicaw::test(name = "renamed")]
+async fn original_name() {}
+
+mod nested {
+    #[mimicaw::test]
+    async fn in_module() {}
+}
+
+// The same function name in another module does not conflict.
+mod other {
+    #[mimicaw::test]
+    async fn in_module() -> Result<(), String> {
+        Err("the test in another module".into())
+    }
+}
+
+#[mimicaw::test(bench)]
+fn bench(mut b: Bencher) -> Outcome {
+    b.iter(|| (0..100u64).sum::<u64>())
+}
+
+/// The reporter that records the order of the collected tests.
+struct Collected {
+    pretty: PrettyReporter,
+    names: Vec<String>,
+}
+
+impl Reporter for Collected {
+    fn suite_start(&mut self, tests: &[TestDesc]) {
+        self.names = tests.iter().map(|desc| desc.name().to_owned()).collect();
+        self.pretty.suite_start(tests);
+    }
+
+    fn test_result(&mut self, desc: &TestDesc, outcome: Option<&Outcome>) {
+        self.pretty.test_result(desc, outcome);
+    }
+
+    fn summary(&mut self, report: &Report) {
+        self.pretty.summary(report);
+    }
+}
+
+fn main() {
+    let args = Args::from_env().unwrap_or_else(|st| st.exit());
+    let mut reporter = Collected {
+        pretty: PrettyReporter::new(&args),
+        names: vec![],
+    };
+    let report = block_on(mimicaw::run_tests_with_reporter(
+        &args,
+        mimicaw::collected_tests(),
+        DefaultRunner::new(),
+        &mut reporter,
+    ))
+    .unwrap_or_else(|st| st.exit());
+
+    let mut errors = vec![];
+    let mut sorted = reporter.names.clone();
+    sorted.sort();
+    if reporter.names != sorted {
+        errors.push(format!(
+            "the tests were not sorted by name: {:?}",
+            reporter.names
+        ));
+    }
+    for &(name, expected) in EXPECTATIONS {
+        match actual(&report, name) {
+            // The test is excluded by the filters given on the command line.
+            None if report.filtered_out.iter().any(|desc| desc.name() == name) => (),
+            None => errors.push(format!("{}: the test was not run", name)),
+            Some(actual) if actual == expected => (),
+            Some(actual) => errors.push(format!(
+                "{}: expected {:?}, but was {:?}",
+                name, expected, actual
+            )),
+        }
+    }
+    let collected = report.passed.len() + report.failed.len() + report.ignored.len();
+    let filtered_out = report.filtered_out.len();
+    if collected + filtered_out != EXPECTATIONS.len() {
+        errors.push(format!(
+            "expected {} collected tests, but was {}",
+            EXPECTATIONS.len(),
+            collected + filtered_out
+        ));
+    }
+
+    if errors.is_empty() {
+        println!("all the test cases behaved as expected");
+    } else {
+        for error in &errors {
+            eprintln!("error: {}", error);
+        }
+        process::exit(101);
+    }
+}
+
+fn actual(report: &Report, name: &str) -> Option<Expect> {
+    if report.passed.iter().any(|desc| desc.name() == name) {
+        Some(Expect::Passed)
+    } else if report.failed.iter().any(|(desc, _)| desc.name() == name) {
+        Some(Expect::Failed)
+    } else if report.ignored.iter().any(|desc| desc.name() == name) {
+        Some(Expect::Ignored)
+    } else {
+        None
+    }
+}